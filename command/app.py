@@ -14,17 +14,24 @@ def send_command(cmd, data=None):
     }))
 
 
-def send_policy(policy, data=None):
+def send_policy(policy, data=None, duration=None):
     # Wrangle arguments into correct format
     args = None
     if len(data) == 1:
         args = data[0]
     elif len(data) > 1:
         args = data
+    policy = {policy: args}
+    if duration:
+        msg = {'TimedPolicy': [policy, duration]}
+    else:
+        msg = {'Policy': policy}
+    redis.lpush('cmds', json.dumps(msg))
+
+
+def send_revert(id):
     redis.lpush('cmds', json.dumps({
-        'Policy': {
-            policy: args
-        }
+        'Revert': id
     }))
 
 
@@ -72,10 +79,26 @@ def policies():
         return jsonify(policies=policies)
     else:
         data = request.get_json()
-        send_policy(data['name'], data['args']);
+        send_policy(data['name'], data['args'], data.get('duration'));
         return jsonify(success=True)
 
 
+@app.route('/policies/active')
+def active_policies():
+    """Get policies currently in effect"""
+    policies = redis.get('policies:active')
+    policies = json.loads(policies.decode('utf8')) if policies else []
+    return jsonify(policies=policies)
+
+
+@app.route('/policies/revert', methods=['POST'])
+def revert_policy():
+    """Revert a policy before the next run"""
+    data = request.get_json()
+    send_revert(data['id'])
+    return jsonify(success=True)
+
+
 @app.route('/step', methods=['POST'])
 def step():
     """Step the simulation"""
@@ -33,6 +33,7 @@ class Command {
     setInterval(() => {
       this.queryStatus();
       this.queryState();
+      this.queryActivePolicies();
     }, 500);
   }
 
@@ -65,6 +66,28 @@ class Command {
     }
   }
 
+  queryActivePolicies() {
+    get('/policies/active', {}, ({policies}) => {
+      let el = this.elements['activePolicies'];
+      el.innerHTML = '';
+      policies.forEach((p) => {
+        let name = Object.keys(p.policy)[0];
+        let until = p.until === null ? 'permanent' : `until step ${p.until}`;
+        let html = `<li>
+          <h4>${name}</h4>
+          <div class="policy--args">${JSON.stringify(p.policy[name])}; from step ${p.applied}, ${until}</div>
+          <button>Revert</button>
+        </li>`;
+        let child = htmlToElement(html);
+        child.querySelector('button').addEventListener('click', () => {
+          post('/policies/revert', {id: p.id}, () => {});
+          child.querySelector('button').disabled = true;
+        });
+        el.appendChild(child);
+      });
+    });
+  }
+
   queryStatus() {
     let el = this.elements['status'];
     get('/status', {}, ({status}) => {
@@ -140,13 +163,17 @@ class Command {
           <div class="policy-item--desc">${spec.desc}</div>
           <div class="policy-item--form">
             ${fields}
+            <div class="policy-item--field">
+              <label>duration (0 = permanent)</label>
+              <input class="policy-item--duration" type="number" min="0" value="0">
+            </div>
             <button disabled=true>Implement &gt;&gt;</button>
           </div>
         </li>`;
 
         // Setup editable inputs
         let child = htmlToElement(html);
-        [...child.querySelectorAll('input')].forEach((input, i) => {
+        [...child.querySelectorAll('.policy-item--input')].forEach((input, i) => {
           let arg = spec.args[i];
           input.addEventListener('change', () => {
             let val;
@@ -202,9 +229,11 @@ class Command {
               .firstChild.querySelector('ul')
               .appendChild(htmlToElement(html));
 
+            let duration = parseInt(child.querySelector('.policy-item--duration').value) || 0;
             post('/policies', {
               name: k,
               args: args.map((a) => a.value),
+              duration: duration > 0 ? duration : null
             }, () => {});
           }
         });
@@ -267,6 +267,7 @@ const command = new Command({
   stepInput: '#step input',
   config: '#config ul',
   policies: '#policy ul',
-  policyHistory: '#policy-history div'
+  policyHistory: '#policy-history div',
+  activePolicies: '#policy-active'
 }, plotter);
 
@@ -142,9 +142,8 @@ const PUBLISHER_SPEC = {
 
 const POLICY_SPEC = {
   'PopulationChange': {
-    desc: 'Change the population by the specified amount.',
+    desc: 'Change the population by the specified amount. Negative amounts remove agents.',
     args: [{
-      min: 0,
       type: 'int',
       name: 'amount',
       default: 100
@@ -194,5 +193,70 @@ const POLICY_SPEC = {
       name: 'amount',
       default: 0.8
     }]
+  },
+  'ModerateExtremeValues': {
+    desc: 'Platforms act on content with any value beyond the threshold. Visibility is the chance it is still shown; 0 removes it.',
+    args: [{
+      min: 0,
+      type: 'float',
+      name: 'threshold',
+      default: 0.8
+    }, {
+      min: 0,
+      type: 'float',
+      name: 'visibility',
+      default: 0
+    }]
+  },
+  'ModerateSensationalism': {
+    desc: 'Platforms act on content whose spectacle exceeds its depth by more than the threshold. Visibility is the chance it is still shown; 0 removes it.',
+    args: [{
+      min: 0,
+      type: 'float',
+      name: 'threshold',
+      default: 0.5
+    }, {
+      min: 0,
+      type: 'float',
+      name: 'visibility',
+      default: 0
+    }]
+  },
+  'ModerateLowTrust': {
+    desc: 'Platforms act on content by authors their users trust less than the threshold, on average. Visibility is the chance it is still shown; 0 removes it.',
+    args: [{
+      min: 0,
+      type: 'float',
+      name: 'threshold',
+      default: 0.2
+    }, {
+      min: 0,
+      type: 'float',
+      name: 'visibility',
+      default: 0
+    }]
+  },
+  'ModerateBots': {
+    desc: 'Platforms act on content by authors with more than the threshold pieces shared at once, as bots tend to. Visibility is the chance it is still shown; 0 removes it.',
+    args: [{
+      min: 0,
+      type: 'float',
+      name: 'threshold',
+      default: 20
+    }, {
+      min: 0,
+      type: 'float',
+      name: 'visibility',
+      default: 0
+    }]
+  },
+  'FoundFactCheckers': {
+    desc: 'Create new fact-checking organizations, which label content as accurate or false.',
+    args: [{
+      min: 0,
+      type: 'int',
+      name: 'amount',
+      default: 2
+    }]
   }
 };
@@ -3,7 +3,8 @@ use serde::{Serialize, Deserialize};
 use std::env;
 use std::fs::File;
 use std::io::BufReader;
-use super::model::SimulationConfig;
+use super::model::{SimulationConfig, Graph, import};
+use super::scenario::{self, Scenario};
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 #[serde(rename_all = "UPPERCASE")]
@@ -22,6 +23,22 @@ pub struct Config {
     #[serde(default)]
     pub seed: u64,
 
+    // Save a checkpoint every this many steps.
+    // 0 disables checkpointing.
+    #[serde(default)]
+    pub checkpoint: usize,
+
+    // Checkpoint directory to resume from
+    #[serde(default)]
+    pub resume: Option<String>,
+
+    // Sweep spec to run in batch mode
+    #[serde(default)]
+    pub sweep: Option<String>,
+
+    // Policies to apply at given steps
+    #[serde(default)]
+    pub scenario: Option<Scenario>,
 }
 
 pub fn load_config() -> Config {
@@ -44,6 +61,25 @@ pub fn load_config() -> Config {
         Err(_) => conf.command,
     };
 
+    conf.checkpoint = match env::var("CHECKPOINT") {
+        Ok(checkpoint) => checkpoint.parse().unwrap(),
+        Err(_) => conf.checkpoint,
+    };
+
+    conf.resume = match env::var("RESUME") {
+        Ok(resume) => Some(resume),
+        Err(_) => conf.resume,
+    };
+
+    conf.sweep = match env::var("SWEEP") {
+        Ok(sweep) => Some(sweep),
+        Err(_) => conf.sweep,
+    };
+
+    if let Ok(path) = env::var("SCENARIO") {
+        conf.scenario = Some(scenario::load(&path));
+    }
+
     let mut rng = rand::thread_rng();
     conf.seed = match env::var("SEED") {
         Ok(seed) => seed.parse().unwrap(),
@@ -59,3 +95,16 @@ impl Config {
         self.simulation = other.simulation.clone();
     }
 }
+
+// A network file decides the population, so it's
+// recorded in the config along with everything else.
+// Call once the config is final, i.e. after any sweep
+// params, and pass the network on to `Simulation::with_network`
+// so the file is only read once.
+pub fn set_network_population(conf: &mut SimulationConfig) -> Option<Graph> {
+    let graph = import::load_network(conf);
+    if let Some(graph) = &graph {
+        conf.population = graph.n_nodes();
+    }
+    graph
+}
@@ -1,7 +1,6 @@
 use serde::Deserialize;
 use redis::{Commands, Connection};
-use super::config::Config;
-use super::model::Policy;
+use jeco::{Config, Policy, PolicyId, PolicyRecord};
 use strum_macros::{Display};
 use strum::IntoEnumIterator;
 
@@ -16,18 +15,21 @@ pub enum Status {
 enum Message {
     Command(Command),                     // steps
     Policy(Policy),
+    TimedPolicy(Policy, usize),           // duration
+    Revert(PolicyId),
 }
 
 #[derive(Display, PartialEq, Debug, Deserialize)]
 pub enum Command {
     Run(usize),
-    Reset(Config)
+    Reset(Box<Config>)
 }
 
 
 pub struct Commander {
     con: Connection,
-    pub policies: Vec<Policy>,
+    pub policies: Vec<(Policy, Option<usize>)>,
+    pub reverts: Vec<PolicyId>,
 }
 
 impl Commander {
@@ -37,12 +39,13 @@ impl Commander {
 
         Commander {
             con: con,
-            policies: Vec::new()
+            policies: Vec::new(),
+            reverts: Vec::new()
         }
     }
 
     fn set_status(&mut self, state: Status) -> redis::RedisResult<()> {
-        self.con.set("status", state.to_string().to_lowercase())?;
+        let _: () = self.con.set("status", state.to_string().to_lowercase())?;
         Ok(())
     }
 
@@ -59,18 +62,24 @@ impl Commander {
     }
 
     pub fn reset(&mut self, conf: &Config) -> redis::RedisResult<()> {
-        self.con.del("cmds")?;
-        self.con.del("state:history")?;
-        self.con.set("state:step", -1)?;
+        let _: () = self.con.del("cmds")?;
+        let _: () = self.con.del("state:history")?;
+        let _: () = self.con.del("policies:active")?;
+        let _: () = self.con.set("state:step", -1)?;
 
         let conf_serialized = serde_json::to_string(conf).unwrap();
-        self.con.set("config", conf_serialized)?;
+        let _: () = self.con.set("config", conf_serialized)?;
 
         let policies: Vec<String> = Policy::iter().map(|p| p.to_string()).collect();
         let policies_serialized = serde_json::to_string(&policies).unwrap();
         self.con.set("policies", policies_serialized)
     }
 
+    pub fn set_active_policies(&mut self, policies: &[PolicyRecord]) -> redis::RedisResult<()> {
+        let policies_serialized = serde_json::to_string(policies).unwrap();
+        self.con.set("policies:active", policies_serialized)
+    }
+
     pub fn wait_for_command(&mut self) -> Command {
         loop {
             let command = self.process_messages();
@@ -93,7 +102,17 @@ impl Commander {
                             command = Some(c)
                         },
                         Message::Policy(p) => {
-                            self.policies.push(p);
+                            self.policies.push((p, None));
+                        },
+                        Message::TimedPolicy(p, duration) => {
+                            if p.is_reversible() {
+                                self.policies.push((p, Some(duration)));
+                            } else {
+                                println!("Refusing timed policy {:?}, it can't be reverted", p);
+                            }
+                        },
+                        Message::Revert(id) => {
+                            self.reverts.push(id);
                         }
                     }
                 }
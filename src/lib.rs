@@ -0,0 +1,18 @@
+pub mod model;
+pub mod config;
+pub mod rec;
+pub mod sweep;
+pub mod scenario;
+
+pub use self::model::{
+    Simulation, SimulationBuilder, SimulationConfig,
+    Agent, AgentId, Publisher, PublisherId, Platform, PlatformId, FactChecker, FactCheckerId,
+    Content, ContentId, SharedContent, Policy, PolicyId, PolicyRecord,
+    FeedRanker, Ranker};
+pub use self::model::snapshot;
+pub use self::config::Config;
+pub use self::rec::Recorder;
+pub use self::scenario::Scenario;
+
+// The RNG every part of the simulation draws from
+pub use rand_chacha::ChaCha20Rng;
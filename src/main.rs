@@ -1,18 +1,16 @@
-mod model;
-mod config;
 mod control;
-mod rec;
 
-use self::rec::Recorder;
+use jeco::{config, sweep, snapshot, Config, Recorder, Simulation};
 use self::control::{Commander, Command};
-use self::model::Simulation;
 use pbr::ProgressBar;
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use rand::SeedableRng;
+use std::fs;
+use std::path::Path;
 
 fn main() {
     let mut conf = config::load_config();
-    let mut rng: StdRng = SeedableRng::seed_from_u64(conf.seed);
+    let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(conf.seed);
 
     let steps = conf.steps;
     let debug = conf.debug;
@@ -24,9 +22,10 @@ fn main() {
 
         loop {
             println!("{:?}", conf);
+            let graph = config::set_network_population(&mut conf.simulation);
             command.reset(&conf).unwrap();
             command.set_loading().unwrap();
-            let mut sim = Simulation::new(&conf.simulation, &mut rng);
+            let mut sim = Simulation::with_network(&conf.simulation, graph, &mut rng);
             let mut recorder = Recorder::new(&sim, &mut rng);
             command.set_ready().unwrap();
 
@@ -38,16 +37,22 @@ fn main() {
                     Command::Run(steps) => {
                         println!("Running for {:?} steps...", steps);
                         command.set_running().unwrap();
-                        for policy in command.policies.drain(..) {
+                        for id in command.reverts.drain(..) {
+                            println!("Reverting policy {:?}", id);
+                            sim.revert_policy(id, &mut conf.simulation, &mut rng);
+                        }
+                        for (policy, duration) in command.policies.drain(..) {
                             println!("Applying policy {:?}", policy);
-                            sim.apply_policy(&policy, &mut conf.simulation, &mut rng);
+                            sim.apply_policy_for(&policy, duration, &mut conf.simulation, &mut rng);
                         }
                         for _ in 0..steps {
+                            sim.expire_policies(&mut conf.simulation, &mut rng);
                             sim.step(&conf.simulation, &mut rng);
                             recorder.record(step, &sim);
                             recorder.sync(step, redis_host).unwrap();
                             step += 1;
                         }
+                        command.set_active_policies(sim.active_policies()).unwrap();
                         command.set_ready().unwrap();
                     },
                     Command::Reset(overrides) => {
@@ -59,22 +64,83 @@ fn main() {
             }
         }
 
+    // Batch mode
+    } else if let Some(spec) = &conf.sweep {
+        sweep::run(spec, &conf);
+
     // Single run mode
     } else {
-        let mut sim = Simulation::new(&conf.simulation, &mut rng);
-        if debug {
-            let mut pb = ProgressBar::new(steps as u64);
-            let mut recorder = Recorder::new(&sim, &mut rng);
-            for step in 0..steps {
-                sim.step(&conf.simulation, &mut rng);
+        let checkpoint_dir = Path::new("runs/checkpoint");
+        let (mut sim, mut recorder, start) = match &conf.resume {
+            Some(dir) => {
+                let dir = Path::new(dir);
+                let snapshot = snapshot::load(&dir.join("sim.snap")).expect("could not load snapshot");
+
+                // Only debug runs have a Recorder to resume
+                let rec_path = dir.join("recorder.json");
+                let recorder: Option<Recorder> = if rec_path.exists() {
+                    Some(serde_json::from_str(
+                        &fs::read_to_string(rec_path).expect("could not read recorder"))
+                        .expect("could not load recorder"))
+                } else {
+                    None
+                };
+                println!("Resuming from step {:?}", snapshot.step);
+                conf.seed = snapshot.seed;
+                conf.simulation = snapshot.conf;
+                rng = snapshot.rng;
+                (snapshot.sim, recorder, snapshot.step)
+            },
+            None => {
+                let graph = config::set_network_population(&mut conf.simulation);
+                let sim = Simulation::with_network(&conf.simulation, graph, &mut rng);
+                let recorder = if debug {
+                    Some(Recorder::new(&sim, &mut rng))
+                } else {
+                    None
+                };
+                (sim, recorder, 0)
+            }
+        };
+
+        let mut pb = ProgressBar::new((steps - start.min(steps)) as u64);
+        for step in start..steps {
+            if let Some(scenario) = &conf.scenario {
+                scenario.apply(step, &mut sim, &mut conf.simulation, &mut rng);
+            }
+            sim.step(&conf.simulation, &mut rng);
+            if let Some(recorder) = &mut recorder {
                 recorder.record(step, &sim);
                 pb.inc();
             }
-            recorder.save(&conf);
-        } else {
-            for _ in 0..steps {
-                sim.step(&conf.simulation, &mut rng);
+            if conf.checkpoint > 0 && (step + 1) % conf.checkpoint == 0 {
+                checkpoint(checkpoint_dir, step + 1, &conf, &sim, recorder.as_ref(), &rng);
             }
         }
+        if let Some(recorder) = &recorder {
+            recorder.save(&conf, &sim);
+        }
+    }
+}
+
+// Write to temporary files first so that
+// a crash mid-write doesn't clobber the last checkpoint
+fn checkpoint(dir: &Path, step: usize, conf: &Config, sim: &Simulation, recorder: Option<&Recorder>, rng: &ChaCha20Rng) {
+    fs::create_dir_all(dir).unwrap();
+    let sim_path = dir.join("sim.snap");
+    let rec_path = dir.join("recorder.json");
+    let sim_tmp = dir.join("sim.snap.tmp");
+    let rec_tmp = dir.join("recorder.json.tmp");
+    snapshot::save(&sim_tmp, step, conf.seed, &conf.simulation, sim, rng).expect("could not write snapshot");
+    if let Some(recorder) = recorder {
+        fs::write(&rec_tmp, serde_json::to_string(recorder).unwrap()).expect("could not write recorder");
+    }
+    fs::rename(sim_tmp, sim_path).unwrap();
+    if recorder.is_some() {
+        fs::rename(rec_tmp, rec_path).unwrap();
+    } else {
+        // Don't leave one from an earlier run
+        // to be resumed along with this one
+        let _ = fs::remove_file(rec_path);
     }
 }
@@ -1,24 +1,52 @@
 use fnv::{FnvHashMap, FnvHashSet};
 use super::grid::{Position};
-use super::util::{Vector, VECTOR_SIZE, Learner};
+use super::util::{Vector, Learner};
 use super::publisher::PublisherId;
 use super::platform::PlatformId;
-use super::content::{Content, ContentId, ContentBody, SharedContent, SharerType};
-use super::config::{SimulationConfig, AgentConfig};
+use super::content::{Content, ContentId, ContentBody, SharedContent, SharerType, Verdict, content_ref};
+use super::config::SimulationConfig;
 use super::motive::Motive;
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use rand::Rng;
 use std::fmt::Debug;
 use std::sync::Arc;
+use serde::{Serialize, Deserialize};
 use super::util;
 
 pub type Topics = Vector;
 pub type Values = Vector;
 pub type AgentId = usize;
 
-#[derive(Debug)]
+// What an Agent did with the content they read
+pub struct Consumed {
+    pub to_share: Vec<Arc<Content>>,
+
+    // New subscriptions and unsubscriptions
+    pub subs: (Vec<PublisherId>, Vec<PublisherId>),
+
+    // New follows and unfollows
+    pub follows: (FnvHashSet<AgentId>, FnvHashSet<AgentId>),
+
+    // Data generated for each Platform
+    pub data: FnvHashMap<PlatformId, f32>,
+
+    // Ad revenue for content creators,
+    // and the Platforms' cut of it
+    pub revenue: FnvHashMap<(SharerType, usize), f32>,
+    pub platform_revenue: FnvHashMap<PlatformId, f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Agent {
     pub id: AgentId,
+
+    // False once the Agent has left the simulation
+    pub alive: bool,
+
+    // Bots are inauthentic accounts run as part
+    // of an influence operation. Their values are fixed.
+    pub bot: bool,
+
     pub interests: Topics,
     pub values: Values,
     pub motive: Motive,
@@ -32,6 +60,10 @@ pub struct Agent {
     // Publishers the Agent is subscribed to
     pub subscriptions: FnvHashSet<PublisherId>,
 
+    // EWMA of how much of the content the Agent
+    // sees on each of their Platforms they reshare
+    pub platform_value: FnvHashMap<PlatformId, f32>,
+
     // Agent's estimate of how likely
     // a Publisher is to publish their content
     pub publishability: f32,
@@ -62,6 +94,7 @@ pub struct Agent {
     pub attention: f32,
 
     // Track most recent content
+    #[serde(with = "content_ref::queue")]
     pub content: util::LimitedQueue<Arc<Content>>,
 
     // Track recently encountered content
@@ -72,33 +105,35 @@ pub struct Agent {
 }
 
 
-pub fn random_values(rng: &mut StdRng) -> Values {
+pub fn random_values(dims: usize, rng: &mut ChaCha20Rng) -> Values {
     // Normal dist, -1 to 1
-    let v_vec = (0..VECTOR_SIZE)
+    let v_vec = (0..dims)
         .map(|_| util::normal_range(rng))
         .collect();
     Values::from_vec(v_vec)
 }
 
-pub fn random_topics(rng: &mut StdRng) -> Topics {
+pub fn random_topics(dims: usize, rng: &mut ChaCha20Rng) -> Topics {
     // Normal dist, 0 to 1
-    let i_vec = (0..VECTOR_SIZE)
+    let i_vec = (0..dims)
         .map(|_| util::normal_p(rng))
         .collect();
     Topics::from_vec(i_vec)
 }
 
 impl Agent {
-    pub fn new(id: AgentId, conf: &AgentConfig, mut rng: &mut StdRng) -> Agent {
+    pub fn new(id: AgentId, conf: &SimulationConfig, mut rng: &mut ChaCha20Rng) -> Agent {
         let resources = util::normal_p(&mut rng);
         let learner = Learner::new(&mut rng);
         let params = learner.get_params();
 
         Agent {
             id: id,
+            alive: true,
+            bot: false,
             location: (0, 0),
-            interests: random_topics(&mut rng),
-            values: random_values(&mut rng),
+            interests: random_topics(conf.topic_dims, rng),
+            values: random_values(conf.value_dims, rng),
             motive: rng.gen(),
             reach: 100.,
             depth: params[0],
@@ -106,7 +141,7 @@ impl Agent {
             ads: params[2],
             attention: params[3],
             learner: learner,
-            attention_budget: conf.attention_budget,
+            attention_budget: conf.agent.attention_budget,
             resources: resources * 100.,
             expenses: 0.,
             media_literacy: util::normal_p(&mut rng),
@@ -114,6 +149,7 @@ impl Agent {
             publishabilities: FnvHashMap::default(),
             publishers: FnvHashMap::default(),
             subscriptions: FnvHashSet::default(),
+            platform_value: FnvHashMap::default(),
             trust: FnvHashMap::default(),
             content: util::LimitedQueue::new(10),
             seen_content: util::LimitedSet::new(100),
@@ -121,7 +157,7 @@ impl Agent {
         }
     }
 
-    pub fn produce(&self, max_attention: f32, rng: &mut StdRng) -> ContentBody {
+    pub fn produce(&self, max_attention: f32, rng: &mut ChaCha20Rng) -> ContentBody {
         // Agent produces something around their own interests and values
         let topics = self.interests.map(|v| util::normal_p_mu_tight(v, rng));
         let values = self.values.map(|v| util::normal_range_mu_tight(v, rng));
@@ -134,13 +170,27 @@ impl Agent {
             cost: attn_cost,
             depth: self.depth,
             spectacle: self.spectacle,
+            veracity: 1.,
             topics: topics,
             values: values,
         }
     }
 
+    // Bots post at a fixed rate, always pushing their own values.
+    // They're funded from elsewhere so posting costs them nothing.
+    pub fn produce_campaign(&self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<ContentBody> {
+        (0..conf.bot.posts).map(|_| {
+            let mut body = self.produce(conf.agent.attention_budget, rng);
+            body.values = self.values.clone();
+            if conf.bot.false_rate > 0. && rng.gen::<f32>() < conf.bot.false_rate {
+                body.veracity = rng.gen::<f32>() * 0.5;
+            }
+            body
+        }).collect()
+    }
+
     // Return content they create
-    pub fn try_produce(&mut self, population: usize, conf: &SimulationConfig, rng: &mut StdRng) -> Option<ContentBody> {
+    pub fn try_produce(&mut self, population: usize, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Option<ContentBody> {
         let cost = (self.depth + self.spectacle) * conf.cost_per_quality;
         if self.resources < cost { return None }
 
@@ -148,7 +198,10 @@ impl Agent {
         // and resources
         let roll: f32 = rng.gen();
         if roll < p_produce(self.reach/population as f32) {
-            let body = self.produce(conf.agent.attention_budget, rng);
+            let mut body = self.produce(conf.agent.attention_budget, rng);
+            if conf.false_rate > 0. && rng.gen::<f32>() < conf.false_rate {
+                body.veracity = rng.gen::<f32>() * 0.5;
+            }
             self.resources -= cost;
             self.expenses += cost;
 
@@ -158,13 +211,16 @@ impl Agent {
         }
     }
 
-    // Return content they decide to share
+    // Return content they decide to share.
+    // Removed Agents' content may still be around,
+    // but no trust is kept for them.
     pub fn consume(
         &mut self,
         content: &Vec<(Option<&PlatformId>, &SharedContent)>,
+        removed: &FnvHashSet<AgentId>,
         conf: &SimulationConfig,
-        rng: &mut StdRng
-    ) -> (Vec<Arc<Content>>, (Vec<PublisherId>, Vec<PublisherId>), (FnvHashSet<AgentId>, FnvHashSet<AgentId>), FnvHashMap<PlatformId, f32>, FnvHashMap<(SharerType, usize), f32>) {
+        rng: &mut ChaCha20Rng
+    ) -> Consumed {
         let mut attention = self.attention_budget;
         let mut to_share = Vec::new();
         let mut new_subs = Vec::new();
@@ -179,6 +235,10 @@ impl Agent {
         // Ad revenue generated for publishers or agents
         let mut revenue = FnvHashMap::default();
 
+        // Platforms' cut of ad revenue
+        // for content read on them
+        let mut platform_revenue = FnvHashMap::default();
+
         for (platform, sc) in content {
             let c = &sc.content;
 
@@ -197,6 +257,29 @@ impl Agent {
                 continue;
             }
             self.seen_content.insert(c.id);
+            c.stats.view();
+
+            // Non-subscribers only see a preview of
+            // paywalled content. It's enough to form an opinion
+            // of the Publisher, but not to share, be influenced by,
+            // or see the ads in.
+            let preview = c.paywalled && match c.publisher {
+                Some(p_id) => !self.subscriptions.contains(&p_id),
+                None => false
+            };
+            if c.ads > 0. && !preview {
+                c.stats.ad_impression();
+            }
+
+            let ad_revenue = c.ads * conf.revenue_per_ad;
+            let cut = match platform {
+                Some(p_id) if c.ads > 0. && !preview => {
+                    let cut = ad_revenue * conf.platform.ad_cut;
+                    *platform_revenue.entry(**p_id).or_insert(0.) += cut;
+                    cut
+                },
+                _ => 0.
+            };
 
             let affinity = similarity(&self.interests, &c.body.topics);
             let align = alignment(&self.values, &c.body.values);
@@ -209,23 +292,19 @@ impl Agent {
                 Some(p_id) => {
                     let relevancy = self.relevancies[p_id];
                     let (v, _) = self.publishers.entry(p_id).or_insert((conf.default_trust, 0));
-                    // println!("update: {:?} {:?} {:?} {:?} {:?}", p_id, update_trust(affinity * relevancy, align)/(c.ads + 1.), affinity, relevancy, align);
-                    // *v = f32::max(0., util::ewma(update_trust(affinity * relevancy, align)/(c.ads + 1.), *v));
-                    // println!("af:{:?} re:{:?} al:{:?}", affinity, relevancy, align);
                     *v = f32::max(0., util::ewma(update_trust((affinity+relevancy)/2., align)/(c.ads/10. + 1.), *v));
-                    // *v = f32::max(0., util::ewma(1., *v));
 
                     seen_publishers.insert(p_id);
 
-                    if c.ads > 0. {
-                        revenue.insert((SharerType::Publisher, p_id), c.ads * conf.revenue_per_ad);
+                    if c.ads > 0. && !preview {
+                        *revenue.entry((SharerType::Publisher, p_id)).or_insert(0.) += ad_revenue - cut;
                     }
 
                     react *= relevancy;
                 },
                 None => {
                     if c.ads > 0. {
-                        revenue.insert((SharerType::Agent, c.author), c.ads * conf.revenue_per_ad);
+                        *revenue.entry((SharerType::Agent, c.author)).or_insert(0.) += ad_revenue - cut;
                     }
 
                     // TODO? Can't access author location so
@@ -237,9 +316,54 @@ impl Agent {
                 }
             }
 
+            if preview {
+                continue;
+            }
+
+            // Agents may see through false content,
+            // depending on their media literacy, or take
+            // a FactChecker's word for it. Either way they
+            // won't share it or be influenced by it,
+            // and trust whoever spread it less.
+            let caught = match c.label.get() {
+                Some(Verdict::Accurate) => false,
+                Some(Verdict::False) if rng.gen::<f32>() < conf.fact_checker.label_trust => true,
+                _ => c.body.veracity < 1. && rng.gen::<f32>() < self.media_literacy * (1. - c.body.veracity)
+            };
+            if caught {
+                c.stats.flag();
+                if let Some(p_id) = c.publisher {
+                    if let Some((v, _)) = self.publishers.get_mut(&p_id) {
+                        *v = f32::max(0., *v - conf.false_penalty);
+                    }
+                }
+                let spreaders = sc.chain.iter().chain(std::iter::once(&sc.sharer))
+                    .filter(|(typ, _)| *typ == SharerType::Agent)
+                    .map(|(_, id)| *id)
+                    .chain(std::iter::once(c.author))
+                    .collect::<FnvHashSet<AgentId>>();
+                for id in spreaders {
+                    if id == self.id || removed.contains(&id) {
+                        continue;
+                    }
+                    let trust = self.trust.entry(id).or_insert(conf.default_trust);
+                    *trust = f32::max(0., *trust - conf.false_penalty);
+                    if *trust < conf.unfollow_trust {
+                        unfollows.insert(id);
+                    }
+                }
+
+                attention -= c.body.cost;
+                if attention <= 0. {
+                    break;
+                }
+                continue;
+            }
+
             // Do they share it?
             let roll: f32 = rng.gen();
             if roll < react {
+                c.stats.share();
                 to_share.push(c.clone());
             }
 
@@ -248,7 +372,9 @@ impl Agent {
                 (SharerType::Agent, id) => {
                     // network.trust(&self.id, &id); // TODO this is redundant?
 
-                    let mut trust = {
+                    let mut trust = if removed.contains(&id) {
+                        conf.default_trust
+                    } else {
                         let trust = self.trust.entry(id).or_insert(conf.default_trust);
                         let old_trust = trust.clone(); // TODO meh
                         // println!("update: {:?} {:?} {:?} {:?}", id, update_trust(affinity, align), affinity, align);
@@ -264,7 +390,7 @@ impl Agent {
                     };
 
                     // Get author as well
-                    if c.author != id {
+                    if c.author != id && !removed.contains(&c.author) {
                         let author_trust = self.trust.entry(c.author).or_insert(conf.default_trust);
                         trust = (trust + *author_trust)/2.;
                         *author_trust = f32::max(0., util::ewma(update_trust(affinity, align)/(c.ads + 1.), *author_trust));
@@ -278,11 +404,25 @@ impl Agent {
                         }
                     }
 
-                    // TODO as it stands right now, Agents' new follows
-                    // will only be authors. How do they encounter new sharers as well?
-                    // There could be a "retweet" like function
-                    // or we have random background changes to their offline networks
-                    // that manifest as follows on platforms too
+                    // And whoever passed it on in between,
+                    // so Agents can discover new sharers.
+                    // As with the author, we don't know if they're
+                    // already following them or not.
+                    for (typ, b_id) in sc.chain.iter() {
+                        if *typ != SharerType::Agent || *b_id == c.author || *b_id == id || *b_id == self.id || removed.contains(b_id) {
+                            continue;
+                        }
+                        let sharer_trust = self.trust.entry(*b_id).or_insert(conf.default_trust);
+                        *sharer_trust = f32::max(0., util::ewma(update_trust(affinity, align), *sharer_trust));
+                        if *sharer_trust < conf.unfollow_trust {
+                            unfollows.insert(*b_id);
+                        } else if *sharer_trust > conf.follow_trust {
+                            follows.insert(*b_id);
+                        }
+                    }
+
+                    // Offline ties change separately,
+                    // see `Simulation::rewire`
 
                     trust
                 },
@@ -321,8 +461,9 @@ impl Agent {
             }
         }
 
-        // Decide on subscriptions
-        // TODO consider costs of subscription
+        // Decide on subscriptions.
+        // Whether they can afford it is
+        // checked by the Simulation, see `willing_to_pay`.
         for (p_id, (affinity, last_seen)) in self.publishers.iter() {
             // println!("{:?} {:?}", p_id, affinity);
             if last_seen >= &conf.unsubscribe_lag {
@@ -343,10 +484,33 @@ impl Agent {
             }
         }
 
-        (to_share, (new_subs, unsubs), (follows, unfollows), data, revenue)
+        Consumed {
+            to_share,
+            subs: (new_subs, unsubs),
+            follows: (follows, unfollows),
+            data,
+            revenue,
+            platform_revenue,
+        }
+    }
+
+    // Whether the Agent would pay `price` per step
+    // for a subscription, given how much they trust the Publisher
+    pub fn willing_to_pay(&self, p_id: &PublisherId, price: f32, conf: &SimulationConfig) -> bool {
+        if price <= 0. {
+            return true;
+        }
+        let trust = match self.publishers.get(p_id) {
+            Some((trust, _)) => *trust,
+            None => conf.default_trust
+        };
+        price <= f32::min(1., trust) * self.resources * conf.willingness_to_pay
     }
 
     pub fn be_influenced(&mut self, other: &Values, gravity_stretch: f32, max_influence: f32, trust: f32) {
+        if self.bot {
+            return;
+        }
         self.values.zip_apply(other, |a_v, c_v| {
             a_v + util::gravity(a_v, c_v, gravity_stretch, max_influence) * trust
         });
@@ -358,12 +522,12 @@ impl Agent {
     }
 
     pub fn n_shares(&self) -> Vec<usize> {
-        self.content.iter().map(|c| Arc::strong_count(c)).collect()
+        self.content.iter().map(|c| c.stats.shares()).collect()
     }
 
     pub fn update_reach(&mut self) {
         let shares = self.n_shares();
-        if shares.len() == 0 {
+        if shares.is_empty() {
             self.reach = 0.;
         } else {
             let mean_shares = shares.iter().fold(0, |acc, v| acc + v) as f32 / shares.len() as f32;
@@ -371,7 +535,7 @@ impl Agent {
         }
     }
 
-    pub fn learn(&mut self, revenue: f32, update: bool, rng: &mut StdRng) {
+    pub fn learn(&mut self, revenue: f32, update: bool, rng: &mut ChaCha20Rng) {
         // Assume reach has been updated
         // Assume expenses are correct
         let profit = revenue - self.expenses;
@@ -393,17 +557,25 @@ impl Agent {
 }
 
 pub fn distance(a: &Vector, b: &Vector) -> f32 {
-    ((a.x - b.x).powf(2.) + (a.y - b.y).powf(2.)).sqrt()
+    (a - b).norm()
+}
+
+// Topics range from 0 to 1 in each dimension
+pub fn max_topic_distance(dims: usize) -> f32 {
+    (dims as f32).sqrt()
+}
+
+// Values range from -1 to 1 in each dimension
+pub fn max_value_distance(dims: usize) -> f32 {
+    2. * (dims as f32).sqrt()
 }
 
-static MAX_TOPIC_DISTANCE: f32 = 1.4142135623730951; // sqrt(2.)
 pub fn similarity(a: &Vector, b: &Vector) -> f32 {
-    1. - distance(a, b)/MAX_TOPIC_DISTANCE
+    1. - distance(a, b)/max_topic_distance(a.len())
 }
 
-static MAX_VALUE_DISTANCE: f32 = 2.8284271247461903; // sqrt(8.)
 pub fn alignment(a: &Vector, b: &Vector) -> f32 {
-    ((1. - distance(a, b)/MAX_VALUE_DISTANCE) - 0.5) * 2.
+    ((1. - distance(a, b)/max_value_distance(a.len())) - 0.5) * 2.
 }
 
 pub fn update_trust(affinity: f32, alignment: f32) -> f32 {
@@ -424,6 +596,7 @@ pub fn p_produce(p_reach: f32) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
 
     #[test]
     fn test_alignment() {
@@ -466,6 +639,28 @@ mod tests {
         assert_eq!(sim, 1.);
     }
 
+    #[test]
+    fn test_higher_dims() {
+        let a = Topics::from_vec(vec![0., 0., 0., 0., 0.]);
+        let b = Topics::from_vec(vec![1., 1., 1., 1., 1.]);
+        assert!(similarity(&a, &b).abs() < 1e-6);
+
+        let a = Values::from_vec(vec![1., 1., 1., 1., 1.]);
+        let b = Values::from_vec(vec![-1., -1., -1., -1., -1.]);
+        assert!((alignment(&a, &b) + 1.).abs() < 1e-6);
+
+        let mut conf = SimulationConfig::default();
+        conf.topic_dims = 3;
+        conf.value_dims = 5;
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let agent = Agent::new(0, &conf, &mut rng);
+        assert_eq!(agent.interests.len(), 3);
+        assert_eq!(agent.values.len(), 5);
+        let body = agent.produce(conf.agent.attention_budget, &mut rng);
+        assert_eq!(body.topics.len(), 3);
+        assert_eq!(body.values.len(), 5);
+    }
+
     #[test]
     fn test_update_trust() {
         // Strong affinity and strong alignment
@@ -0,0 +1,92 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use super::sim::Simulation;
+use super::config::{SimulationConfig, SinglePublisherConfig, SinglePlatformConfig};
+
+// Constructs Simulations programmatically,
+// starting from the default config, e.g.:
+//
+//   let builder = SimulationBuilder::new()
+//       .population(500)
+//       .platforms(2)
+//       .seed(42);
+//   let (mut sim, mut rng) = builder.build();
+//   sim.step(builder.config(), &mut rng);
+pub struct SimulationBuilder {
+    conf: SimulationConfig,
+    seed: u64,
+}
+
+impl Default for SimulationBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulationBuilder {
+    pub fn new() -> SimulationBuilder {
+        SimulationBuilder::from_config(SimulationConfig::default())
+    }
+
+    pub fn from_config(conf: SimulationConfig) -> SimulationBuilder {
+        SimulationBuilder {
+            conf,
+            seed: 0,
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> SimulationBuilder {
+        self.seed = seed;
+        self
+    }
+
+    pub fn grid_size(mut self, grid_size: usize) -> SimulationBuilder {
+        self.conf.grid_size = grid_size;
+        self
+    }
+
+    pub fn population(mut self, population: usize) -> SimulationBuilder {
+        self.conf.population = population;
+        self
+    }
+
+    pub fn publishers(mut self, n_publishers: usize) -> SimulationBuilder {
+        self.conf.n_publishers = n_publishers;
+        self
+    }
+
+    pub fn platforms(mut self, n_platforms: usize) -> SimulationBuilder {
+        self.conf.n_platforms = n_platforms;
+        self
+    }
+
+    // Add a specifically-configured Publisher
+    pub fn publisher(mut self, publisher: SinglePublisherConfig) -> SimulationBuilder {
+        self.conf.publishers.push(publisher);
+        self.conf.n_publishers = self.conf.n_publishers.max(self.conf.publishers.len());
+        self
+    }
+
+    // Add a specifically-configured Platform
+    pub fn platform(mut self, platform: SinglePlatformConfig) -> SimulationBuilder {
+        self.conf.platforms.push(platform);
+        self.conf.n_platforms = self.conf.n_platforms.max(self.conf.platforms.len());
+        self
+    }
+
+    // For any other config fields
+    pub fn configure<F: FnOnce(&mut SimulationConfig)>(mut self, f: F) -> SimulationBuilder {
+        f(&mut self.conf);
+        self
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.conf
+    }
+
+    pub fn build(&self) -> (Simulation, ChaCha20Rng) {
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(self.seed);
+        let sim = Simulation::new(&self.conf, &mut rng);
+        (sim, rng)
+    }
+}
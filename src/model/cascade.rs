@@ -0,0 +1,195 @@
+use super::content::{ContentId, SharerType};
+use super::agent::AgentId;
+use super::platform::PlatformId;
+use super::publisher::PublisherId;
+use serde::{Serialize, Deserialize};
+
+// How a share reached the sharer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Channel {
+    // Posted by its author or publisher
+    Origin,
+    Offline,
+    Subscription,
+    Platform(PlatformId),
+
+    // Reshared by another bot in the same operation
+    Coordinated,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareEvent {
+    pub sharer: (SharerType, usize),
+
+    // Index of the share this one came from,
+    // None for the root
+    pub parent: Option<usize>,
+    pub channel: Channel,
+    pub step: usize,
+    pub depth: usize,
+}
+
+// How a piece of content spread, as a tree
+// of share events rooted at its author or publisher
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cascade {
+    pub events: Vec<ShareEvent>,
+}
+
+// The Cascade of Content that has expired,
+// along with what's needed to export it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiredCascade {
+    pub id: ContentId,
+    pub author: AgentId,
+    pub publisher: Option<PublisherId>,
+    pub cascade: Cascade,
+}
+
+impl Cascade {
+    pub fn new(root: (SharerType, usize), step: usize) -> Cascade {
+        Cascade {
+            events: vec![ShareEvent {
+                sharer: root,
+                parent: None,
+                channel: Channel::Origin,
+                step,
+                depth: 0,
+            }]
+        }
+    }
+
+    // `from` is who the sharer got the content from.
+    // If they aren't part of the cascade,
+    // the share is attached to the root.
+    pub fn add_share(&mut self, sharer: (SharerType, usize), from: (SharerType, usize), channel: Channel, step: usize) {
+        let parent = self.events.iter().rposition(|e| e.sharer == from).unwrap_or(0);
+        let depth = self.events[parent].depth + 1;
+        self.events.push(ShareEvent {
+            sharer,
+            parent: Some(parent),
+            channel,
+            step,
+            depth,
+        });
+    }
+
+    // Step the content was posted
+    pub fn started(&self) -> usize {
+        self.events[0].step
+    }
+
+    // Number of shares, including the original post
+    pub fn size(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.events.iter().map(|e| e.depth).max().unwrap_or(0)
+    }
+
+    // Number of shares at each depth,
+    // starting at depth 1
+    pub fn shares(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.depth()];
+        for e in &self.events[1..] {
+            counts[e.depth-1] += 1;
+        }
+        counts
+    }
+
+    // Most shares at any one depth
+    pub fn breadth(&self) -> usize {
+        self.shares().into_iter().max().unwrap_or(0)
+    }
+
+    // Mean distance between all pairs of shares
+    // (Goel et al. 2016), computed from the number
+    // of pairs each edge of the tree separates.
+    pub fn structural_virality(&self) -> f32 {
+        let n = self.events.len();
+        if n < 2 { return 0. }
+
+        // Children always come after their parents
+        let mut subtree = vec![1; n];
+        for i in (1..n).rev() {
+            if let Some(parent) = self.events[i].parent {
+                subtree[parent] += subtree[i];
+            }
+        }
+        let total: usize = subtree[1..].iter().map(|s| s * (n - s)).sum();
+        2. * total as f32 / (n * (n - 1)) as f32
+    }
+
+    // Steps from the original post until
+    // the step with the most shares
+    pub fn time_to_peak(&self) -> usize {
+        let start = self.started();
+        let end = self.events.iter().map(|e| e.step).max().unwrap_or(start);
+        let mut counts = vec![0; end - start + 1];
+        for e in &self.events[1..] {
+            counts[e.step - start] += 1;
+        }
+
+        // Earliest step if there are ties
+        let peak = counts.iter().max().cloned().unwrap_or(0);
+        counts.iter().position(|c| *c == peak).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cascade_depth_and_breadth() {
+        let author = (SharerType::Agent, 0);
+        let mut cascade = Cascade::new(author, 0);
+        assert_eq!(cascade.depth(), 0);
+        assert_eq!(cascade.breadth(), 0);
+
+        cascade.add_share((SharerType::Agent, 1), author, Channel::Offline, 1);
+        cascade.add_share((SharerType::Agent, 2), author, Channel::Offline, 1);
+        cascade.add_share((SharerType::Agent, 3), (SharerType::Agent, 1), Channel::Offline, 2);
+        cascade.add_share((SharerType::Agent, 4), (SharerType::Agent, 3), Channel::Offline, 3);
+        assert_eq!(cascade.shares(), vec![2, 1, 1]);
+        assert_eq!(cascade.depth(), 3);
+        assert_eq!(cascade.breadth(), 2);
+        assert_eq!(cascade.size(), 5);
+    }
+
+    #[test]
+    fn cascade_metrics() {
+        let author = (SharerType::Agent, 0);
+        let mut cascade = Cascade::new(author, 5);
+        assert_eq!(cascade.size(), 1);
+        assert_eq!(cascade.structural_virality(), 0.);
+
+        // A star: everyone shares from the author
+        cascade.add_share((SharerType::Agent, 1), author, Channel::Offline, 6);
+        cascade.add_share((SharerType::Agent, 2), author, Channel::Platform(0), 6);
+        cascade.add_share((SharerType::Agent, 3), author, Channel::Platform(0), 7);
+        assert_eq!(cascade.depth(), 1);
+        assert_eq!(cascade.breadth(), 3);
+        assert_eq!(cascade.time_to_peak(), 1);
+
+        // Pairwise distances: 3 pairs of 1, 3 pairs of 2
+        assert_eq!(cascade.structural_virality(), 1.5);
+
+        // A chain
+        let mut cascade = Cascade::new(author, 0);
+        cascade.add_share((SharerType::Agent, 1), author, Channel::Offline, 1);
+        cascade.add_share((SharerType::Agent, 2), (SharerType::Agent, 1), Channel::Offline, 2);
+        cascade.add_share((SharerType::Agent, 3), (SharerType::Agent, 2), Channel::Offline, 3);
+        assert_eq!(cascade.depth(), 3);
+        assert_eq!(cascade.breadth(), 1);
+
+        // Pairwise distances: 3 of 1, 2 of 2, 1 of 3
+        assert_eq!(cascade.structural_virality(), 10./6.);
+
+        // Unknown sharers attach to the root
+        cascade.add_share((SharerType::Agent, 4), (SharerType::Agent, 9), Channel::Subscription, 3);
+        assert_eq!(cascade.events[4].parent, Some(0));
+        assert_eq!(cascade.time_to_peak(), 3);
+    }
+}
@@ -1,4 +1,8 @@
 use super::motive::Motive;
+use super::feed::Ranker;
+use super::content::ContentExpiry;
+use super::publisher::Pricing;
+use super::moderation::Rule;
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
@@ -51,6 +55,11 @@ pub struct SimulationConfig {
     pub unsubscribe_trust: f32,
     pub subscribe_trust: f32,
 
+    // Most of their resources Agents will
+    // spend per step on a subscription they fully trust
+    #[serde(default = "default_willingness_to_pay")]
+    pub willingness_to_pay: f32,
+
     // Agents unsubscribe from Publishers
     // if they don't see Content from them for
     // this many steps
@@ -73,13 +82,89 @@ pub struct SimulationConfig {
     // General strength of the economy
     pub economy: f32,
 
+    // How many steps Publishers' content stays
+    // in their outboxes, and content Agents share
+    // stays in their share queues
+    #[serde(default = "default_window")]
+    pub outbox_window: usize,
+    #[serde(default = "default_window")]
+    pub share_window: usize,
+
+    // How much less likely content is to be read
+    // for each step since it was shared
+    #[serde(default = "default_decay")]
+    pub freshness_decay: f32,
+
+    // When to drop old Content,
+    // to keep memory use down on long runs
+    #[serde(default = "default_expiry")]
+    pub content_expiry: ContentExpiry,
+
+    // Expected fraction of the population
+    // that joins/leaves each step
+    #[serde(default)]
+    pub birth_rate: f32,
+    #[serde(default)]
+    pub death_rate: f32,
+
+    // Chance a piece of an Agent's content is false,
+    // and how much trust Agents lose in whoever
+    // they catch spreading false content
+    #[serde(default)]
+    pub false_rate: f32,
+    #[serde(default)]
+    pub false_penalty: f32,
+
+    // FactCheckers at the start of the simulation
+    #[serde(default)]
+    pub n_fact_checkers: usize,
+
+    // Offline network to load instead of generating one.
+    // The population is however many nodes it has.
+    #[serde(default)]
+    pub network_file: Option<NetworkFile>,
+
+    // Chance each Agent looks for a new offline tie
+    // per step, and the trust below which they drop one
+    #[serde(default)]
+    pub rewire_rate: f32,
+    #[serde(default)]
+    pub dissolve_trust: f32,
+
+    // Bots added on top of the population
+    #[serde(default)]
+    pub n_bots: usize,
+
+    // Number of topic and value dimensions
+    #[serde(default = "default_dims")]
+    pub topic_dims: usize,
+    #[serde(default = "default_dims")]
+    pub value_dims: usize,
+
     // See below
     pub publisher: PublisherConfig,
     pub agent: AgentConfig,
+    #[serde(default)]
+    pub platform: PlatformConfig,
+    #[serde(default)]
+    pub fact_checker: FactCheckerConfig,
+    #[serde(default)]
+    pub bot: BotConfig,
 
     pub publishers: Vec<SinglePublisherConfig>,
+    #[serde(default)]
+    pub platforms: Vec<SinglePlatformConfig>,
 }
 
+fn default_window() -> usize { 1 }
+fn default_willingness_to_pay() -> f32 { 0.1 }
+fn default_pricing() -> Pricing { Pricing::Fixed(0.) }
+fn default_decay() -> f32 { 0.5 }
+fn default_expiry() -> ContentExpiry { ContentExpiry::Never }
+
+// 2 so they can be plotted in 2d
+fn default_dims() -> usize { 2 }
+
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct PublisherConfig {
@@ -89,6 +174,36 @@ pub struct PublisherConfig {
 
     // Base budget for Publishers
     pub base_budget: f32,
+
+    // Publishers with a budget below this
+    // for `bankruptcy_steps` steps in a row shut down.
+    // 0 steps means they never do.
+    #[serde(default)]
+    pub bankruptcy_budget: f32,
+    #[serde(default)]
+    pub bankruptcy_steps: usize,
+
+    // Probability per step that a new Publisher
+    // enters, scaled by the fraction of Agents
+    // who aren't subscribed to any Publisher
+    #[serde(default)]
+    pub entry_rate: f32,
+
+    // Subscription price per step
+    #[serde(default = "default_pricing")]
+    pub pricing: Pricing,
+
+    // Whether non-subscribers only get
+    // a preview of Publishers' content
+    #[serde(default)]
+    pub paywall: bool,
+
+    // Most chance Publishers catch false pitches;
+    // each learns how much to check, up to this.
+    // Checking costs as much per piece
+    // as the same amount of quality.
+    #[serde(default)]
+    pub fact_checking: f32,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
@@ -96,7 +211,163 @@ pub struct PublisherConfig {
 pub struct SinglePublisherConfig {
     // Base budget for Publisher
     pub base_budget: f32,
-    pub motive: Motive
+    pub motive: Motive,
+
+    // Fixed subscription price and paywall,
+    // overriding `PUBLISHER.PRICING`/`PUBLISHER.PAYWALL`
+    #[serde(default)]
+    pub price: Option<f32>,
+    #[serde(default)]
+    pub paywall: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE", default)]
+pub struct PlatformConfig {
+    // How much content ranked feeds
+    // show an Agent per step
+    pub feed_size: usize,
+
+    // Feed algorithm for Platforms
+    // that aren't configured individually
+    pub ranker: Ranker,
+
+    // Standard deviation of the noise in what
+    // Platforms observe of Agents' engagement,
+    // which they use to estimate trust between Agents
+    pub trust_noise: f32,
+
+    // Share of ad revenue for content read on a Platform
+    // that the Platform takes
+    pub ad_cut: f32,
+
+    // Platforms start with this budget and
+    // pay the operating cost each step
+    pub base_budget: f32,
+    pub operating_cost: f32,
+
+    // Platforms that are in the red for this
+    // many steps in a row shut down (0 = never)
+    pub shutdown_steps: usize,
+
+    // Agents leave a Platform if the share of content
+    // they engage with there falls below `leave_value`,
+    // or the share of their friends on it below `leave_friends`
+    pub leave_value: f32,
+    pub leave_friends: f32,
+
+    // Rules Platforms moderate content by,
+    // and how often they get it wrong
+    pub moderation: Vec<Rule>,
+    pub false_positive_rate: f32,
+    pub false_negative_rate: f32,
+}
+
+impl Default for PlatformConfig {
+    fn default() -> PlatformConfig {
+        PlatformConfig {
+            feed_size: 50,
+            ranker: Ranker::Trust,
+            trust_noise: 0.1,
+            ad_cut: 0.,
+            base_budget: 100.,
+            operating_cost: 0.,
+            shutdown_steps: 0,
+            leave_value: 0.,
+            leave_friends: 0.,
+            moderation: Vec::new(),
+            false_positive_rate: 0.,
+            false_negative_rate: 0.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE", default)]
+pub struct FactCheckerConfig {
+    // What each FactChecker gets to spend per step,
+    // and what checking a piece of content costs
+    pub budget: f32,
+    pub cost_per_check: f32,
+
+    // Share of unlabeled content in circulation
+    // each FactChecker tries to check per step
+    pub coverage: f32,
+
+    // Chance an Agent believes a false label
+    pub label_trust: f32,
+
+    // Chance Platforms still show content
+    // labeled false in feeds (1 = no effect)
+    pub platform_visibility: f32,
+}
+
+impl Default for FactCheckerConfig {
+    fn default() -> FactCheckerConfig {
+        FactCheckerConfig {
+            budget: 10.,
+            cost_per_check: 1.,
+            coverage: 0.1,
+            label_trust: 0.5,
+            platform_visibility: 1.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct NetworkFile {
+    // GraphML (`.graphml`) or an edge list
+    pub path: String,
+
+    // Node attributes for an edge list, as CSV
+    #[serde(default)]
+    pub nodes: Option<String>,
+
+    // Whether the edge list's ties only go one way
+    #[serde(default)]
+    pub directed: bool,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE", default)]
+pub struct BotConfig {
+    // Values every bot pushes. If empty,
+    // bots share the first bot's values
+    pub values: Vec<f32>,
+
+    // Pieces each bot posts per step,
+    // and the chance a piece is false
+    pub posts: usize,
+    pub false_rate: f32,
+
+    // Users each bot follows on each of its
+    // Platforms per step, and the chance they follow back
+    pub follows: usize,
+    pub follow_back: f32,
+
+    // Chance a bot reshares each piece
+    // the other bots posted that step
+    pub amplify: f32,
+}
+
+impl Default for BotConfig {
+    fn default() -> BotConfig {
+        BotConfig {
+            values: Vec::new(),
+            posts: 5,
+            false_rate: 0.,
+            follows: 5,
+            follow_back: 0.1,
+            amplify: 1.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct SinglePlatformConfig {
+    pub ranker: Ranker,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
@@ -126,20 +397,46 @@ impl SimulationConfig {
             follow_trust: 0.9,
             unsubscribe_trust: 0.1,
             subscribe_trust: 0.9,
+            willingness_to_pay: 0.1,
             unsubscribe_lag: 20,
             base_conversion_rate: 0.01,
             economy: 1.,
             max_conversion_rate: 0.05,
             max_shared_content: 200,
             cost_per_quality: 0.5,
+            outbox_window: 1,
+            share_window: 1,
+            freshness_decay: 0.5,
+            content_expiry: ContentExpiry::Never,
+            birth_rate: 0.,
+            death_rate: 0.,
+            false_rate: 0.,
+            false_penalty: 0.,
+            n_fact_checkers: 0,
+            network_file: None,
+            rewire_rate: 0.,
+            dissolve_trust: 0.,
+            n_bots: 0,
+            topic_dims: 2,
+            value_dims: 2,
             publisher: PublisherConfig {
                 revenue_per_subscriber: 0.01,
-                base_budget: 2000.
+                base_budget: 2000.,
+                bankruptcy_budget: 0.,
+                bankruptcy_steps: 0,
+                entry_rate: 0.,
+                pricing: Pricing::Fixed(0.),
+                paywall: false,
+                fact_checking: 0.,
             },
             agent: AgentConfig {
                 attention_budget: 20.
             },
-            publishers: Vec::new()
+            platform: PlatformConfig::default(),
+            fact_checker: FactCheckerConfig::default(),
+            bot: BotConfig::default(),
+            publishers: Vec::new(),
+            platforms: Vec::new()
         }
     }
 }
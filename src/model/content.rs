@@ -1,36 +1,288 @@
-use uuid::Uuid;
+use uuid::{Uuid, Builder, Variant, Version};
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, AtomicU8, Ordering};
+use serde::{Serialize, Deserialize};
 use super::agent::{Topics, Values, AgentId};
 use super::publisher::PublisherId;
 
 pub type ContentId = Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Ids are drawn from the Simulation's RNG
+// so that seeded and resumed runs match
+pub fn new_content_id(rng: &mut ChaCha20Rng) -> ContentId {
+    Builder::from_bytes(rng.gen())
+        .set_variant(Variant::RFC4122)
+        .set_version(Version::Random)
+        .build()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SharerType {
     Agent,
     Publisher
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Content {
     pub id: Uuid,
     pub publisher: Option<PublisherId>,
     pub author: AgentId,
     pub body: ContentBody,
     pub ads: f32,
+    pub stats: ContentStats,
+
+    // Only the Publisher's subscribers
+    // can read all of it
+    pub paywalled: bool,
+
+    // Fact-checkers' verdict, if it's been checked
+    pub label: Label,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Verdict {
+    Accurate,
+    False,
+}
+
+// Set after the Content has been shared around,
+// so it's stored atomically like its stats
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Label(AtomicU8);
+
+impl Label {
+    pub fn set(&self, verdict: Verdict) {
+        let v = match verdict {
+            Verdict::Accurate => 1,
+            Verdict::False => 2,
+        };
+        self.0.store(v, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> Option<Verdict> {
+        match self.0.load(Ordering::Relaxed) {
+            1 => Some(Verdict::Accurate),
+            2 => Some(Verdict::False),
+            _ => None
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
+// Counted where they happen, which can be
+// from several threads at once.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContentStats {
+    views: AtomicUsize,
+    shares: AtomicUsize,
+    ad_impressions: AtomicUsize,
+
+    // Times it was seen through as false
+    flags: AtomicUsize,
+}
+
+impl ContentStats {
+    pub fn view(&self) {
+        self.views.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn share(&self) {
+        self.shares.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ad_impression(&self) {
+        self.ad_impressions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn views(&self) -> usize {
+        self.views.load(Ordering::Relaxed)
+    }
+
+    pub fn shares(&self) -> usize {
+        self.shares.load(Ordering::Relaxed)
+    }
+
+    pub fn ad_impressions(&self) -> usize {
+        self.ad_impressions.load(Ordering::Relaxed)
+    }
+
+    pub fn flag(&self) {
+        self.flags.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn flags(&self) -> usize {
+        self.flags.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentBody {
     pub cost: f32,
     pub depth: f32,
     pub spectacle: f32,
+
+    // How truthful the content is, from 0 to 1
+    pub veracity: f32,
+
     pub topics: Topics,
     pub values: Values,
 }
 
-#[derive(Debug)]
+impl ContentBody {
+    pub fn is_false(&self) -> bool {
+        self.veracity < 0.5
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SharedContent {
+    #[serde(with = "content_ref")]
     pub content: Arc<Content>,
     pub sharer: (SharerType, usize),
+
+    // Who it was shared by before `sharer`,
+    // starting with whoever first shared it
+    pub chain: Vec<(SharerType, usize)>,
+
+    // First step it can be read in
+    pub shared_at: usize,
+}
+
+impl SharedContent {
+    // The same content, passed on by an Agent
+    pub fn reshare(&self, by: usize, step: usize) -> SharedContent {
+        let mut chain = self.chain.clone();
+        chain.push(self.sharer);
+        SharedContent {
+            content: self.content.clone(),
+            sharer: (SharerType::Agent, by),
+            chain,
+            shared_at: step,
+        }
+    }
+
+    // Steps since it could first be read
+    pub fn age(&self, step: usize) -> usize {
+        step.saturating_sub(self.shared_at)
+    }
+
+    // How many times the content was passed on
+    // to get here. Original posts are 0.
+    pub fn depth(&self) -> usize {
+        self.chain.len()
+    }
+}
+
+// When Content is dropped from the Simulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ContentExpiry {
+    Never,
+
+    // This many steps after it was posted
+    Ttl(usize),
+
+    // Once it's no longer in any share queue,
+    // outbox or archive
+    Unreferenced,
+}
+
+// Totals for Content that has been dropped,
+// so it still counts towards overall stats
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExpiredContent {
+    pub n: usize,
+    pub views: usize,
+    pub shares: usize,
+    pub ad_impressions: usize,
+    pub max_shares: usize,
+    pub max_cascade_depth: usize,
+}
+
+impl ExpiredContent {
+    pub fn add(&mut self, content: &Content) {
+        let shares = content.stats.shares();
+        self.n += 1;
+        self.views += content.stats.views();
+        self.shares += shares;
+        self.ad_impressions += content.stats.ad_impressions();
+        self.max_shares = self.max_shares.max(shares);
+    }
+}
+
+// Content is shared through `Arc`s, so
+// references to it are serialized as ids
+// and resolved against a registry when loading.
+// This way each piece of content is stored once
+// and restored references share the same allocation.
+pub mod content_ref {
+    use super::{Content, ContentId};
+    use super::super::util::LimitedQueue;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de::Error;
+    use fnv::FnvHashMap;
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    thread_local! {
+        static REGISTRY: RefCell<FnvHashMap<ContentId, Arc<Content>>> = RefCell::new(FnvHashMap::default());
+    }
+
+    // Make the provided content resolvable
+    // while `f` deserializes references to it
+    pub fn with_registry<T, F: FnOnce() -> T>(content: Vec<Content>, f: F) -> T {
+        REGISTRY.with(|r| {
+            let mut registry = r.borrow_mut();
+            for c in content {
+                registry.insert(c.id, Arc::new(c));
+            }
+        });
+        let result = f();
+        REGISTRY.with(|r| r.borrow_mut().clear());
+        result
+    }
+
+    fn resolve<E: Error>(id: ContentId) -> Result<Arc<Content>, E> {
+        REGISTRY.with(|r| r.borrow().get(&id).cloned())
+            .ok_or_else(|| E::custom(format!("unknown content {}", id)))
+    }
+
+    pub fn serialize<S: Serializer>(content: &Arc<Content>, serializer: S) -> Result<S::Ok, S::Error> {
+        content.id.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<Content>, D::Error> {
+        resolve(ContentId::deserialize(deserializer)?)
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(content: &[Arc<Content>], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(content.iter().map(|c| c.id))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Arc<Content>>, D::Error> {
+            Vec::<ContentId>::deserialize(deserializer)?
+                .into_iter()
+                .map(resolve)
+                .collect()
+        }
+    }
+
+    pub mod queue {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(content: &LimitedQueue<Arc<Content>>, serializer: S) -> Result<S::Ok, S::Error> {
+            let ids: Vec<ContentId> = content.iter().map(|c| c.id).collect();
+            (content.capacity(), ids).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LimitedQueue<Arc<Content>>, D::Error> {
+            let (capacity, ids) = <(usize, Vec<ContentId>)>::deserialize(deserializer)?;
+            let mut queue = LimitedQueue::new(capacity);
+            queue.extend(ids.into_iter().map(resolve).collect::<Result<Vec<_>, _>>()?);
+            Ok(queue)
+        }
+    }
 }
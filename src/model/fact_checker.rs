@@ -0,0 +1,123 @@
+use super::content::{Content, Verdict};
+use super::config::FactCheckerConfig;
+use rand_chacha::ChaCha20Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+
+pub type FactCheckerId = usize;
+
+// A FactChecker samples content in circulation
+// and labels it as accurate or false.
+// Labels are seen by everyone who reads the content.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FactChecker {
+    pub id: FactCheckerId,
+
+    // False once the FactChecker has closed
+    pub active: bool,
+
+    // What the FactChecker has left to spend.
+    // It gets more each step and keeps what it doesn't spend.
+    pub budget: f32,
+
+    // Share of unlabeled content
+    // it tries to check each step
+    pub coverage: f32,
+
+    // What was checked in the last step,
+    // and how much of it was labeled false
+    pub n_checked: usize,
+    pub n_labeled_false: usize,
+}
+
+impl FactChecker {
+    pub fn new(id: FactCheckerId, conf: &FactCheckerConfig) -> FactChecker {
+        FactChecker {
+            id,
+            active: true,
+            budget: conf.budget,
+            coverage: conf.coverage,
+            n_checked: 0,
+            n_labeled_false: 0,
+        }
+    }
+
+    // Label a sample of the content, as much as the budget allows.
+    // Content another FactChecker got to first is skipped.
+    pub fn check(&mut self, content: &[&Arc<Content>], conf: &FactCheckerConfig, rng: &mut ChaCha20Rng) {
+        self.n_checked = 0;
+        self.n_labeled_false = 0;
+
+        let affordable = if conf.cost_per_check > 0. {
+            (self.budget / conf.cost_per_check) as usize
+        } else {
+            content.len()
+        };
+        let n = ((content.len() as f32 * self.coverage).round() as usize).min(affordable);
+        for c in content.choose_multiple(rng, n) {
+            if c.label.get().is_some() {
+                continue;
+            }
+            self.budget -= conf.cost_per_check;
+            if c.body.is_false() {
+                c.label.set(Verdict::False);
+                self.n_labeled_false += 1;
+            } else {
+                c.label.set(Verdict::Accurate);
+            }
+            self.n_checked += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::content::ContentBody;
+    use super::super::testing::{new_body, new_content};
+    use rand::SeedableRng;
+
+    fn content(veracity: f32) -> Arc<Content> {
+        Arc::new(new_content(0, None, ContentBody {
+            veracity,
+            ..new_body()
+        }))
+    }
+
+    #[test]
+    fn labels_content() {
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let conf = FactCheckerConfig {
+            coverage: 1.,
+            cost_per_check: 1.,
+            budget: 6.,
+            ..FactCheckerConfig::default()
+        };
+        let content: Vec<Arc<Content>> = (0..10)
+            .map(|i| content(if i < 5 { 0. } else { 1. }))
+            .collect();
+        let refs: Vec<&Arc<Content>> = content.iter().collect();
+
+        // Only as much as the budget allows
+        let mut checker = FactChecker::new(0, &conf);
+        checker.check(&refs, &conf, &mut rng);
+        assert_eq!(checker.n_checked, 6);
+        assert_eq!(checker.budget, 0.);
+        assert_eq!(content.iter().filter(|c| c.label.get().is_some()).count(), 6);
+        for c in &content {
+            match c.label.get() {
+                Some(Verdict::False) => assert!(c.body.is_false()),
+                Some(Verdict::Accurate) => assert!(!c.body.is_false()),
+                None => {}
+            }
+        }
+        assert_eq!(checker.n_labeled_false,
+                   content.iter().filter(|c| c.label.get() == Some(Verdict::False)).count());
+
+        // Already labeled content isn't checked again
+        let mut other = FactChecker::new(1, &conf);
+        other.check(&refs, &conf, &mut rng);
+        assert_eq!(other.n_checked, content.iter().filter(|c| c.label.get().is_some()).count() - 6);
+    }
+}
@@ -0,0 +1,256 @@
+use super::agent::{Agent, AgentId, similarity, alignment};
+use super::platform::Platform;
+use super::content::{SharedContent, ContentBody};
+use super::config::SimulationConfig;
+use rand_chacha::ChaCha20Rng;
+use rand::seq::SliceRandom;
+use rand_distr::{Distribution, Binomial};
+use itertools::Itertools;
+use serde::{Serialize, Deserialize};
+use strum_macros::{Display, EnumIter};
+
+// A FeedRanker decides which of the content
+// shared on a Platform an Agent encounters there
+pub trait FeedRanker {
+    fn feed<'a>(&self,
+                agent: &Agent,
+                platform: &Platform,
+                share_queues: &'a [Vec<SharedContent>],
+                conf: &SimulationConfig,
+                rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent>;
+}
+
+// Which FeedRanker a Platform uses
+#[derive(Display, EnumIter, PartialEq, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum Ranker {
+    Chronological,
+    Engagement,
+    Trust,
+    Similarity,
+    Random,
+}
+
+impl Ranker {
+    pub fn ranker(&self) -> &'static dyn FeedRanker {
+        match self {
+            Ranker::Chronological => &Chronological,
+            Ranker::Engagement => &Engagement,
+            Ranker::Trust => &TrustWeighted,
+            Ranker::Similarity => &ForYou,
+            Ranker::Random => &RandomFeed,
+        }
+    }
+}
+
+// Content shared by the accounts the Agent follows
+fn followed_content<'a>(agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>]) -> Vec<&'a SharedContent> {
+    platform.following_ids(&agent.id).iter()
+        .flat_map(|a_id| share_queues[*a_id].iter())
+        .collect()
+}
+
+// Everything followed accounts shared, unranked,
+// newest first. Queues can hold shares from several
+// steps (see `share_window`), so they're ordered by
+// when they were shared. Shares from the same step
+// are in the reverse of the order they were shared in.
+pub struct Chronological;
+impl FeedRanker for Chronological {
+    fn feed<'a>(&self, agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, _rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        platform.following_ids(&agent.id).iter().rev()
+            .flat_map(|a_id| share_queues[*a_id].iter().rev())
+            .sorted_by(|a, b| b.shared_at.cmp(&a.shared_at))
+            .take(conf.platform.feed_size)
+            .collect()
+    }
+}
+
+// Followed content with the most shares first
+pub struct Engagement;
+impl FeedRanker for Engagement {
+    fn feed<'a>(&self, agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, _rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        followed_content(agent, platform, share_queues).into_iter()
+            .sorted_by(|a, b| b.content.stats.shares().cmp(&a.content.stats.shares()))
+            .take(conf.platform.feed_size)
+            .collect()
+    }
+}
+
+// "Algorithmic" rating based on the Agent's trust of
+// the sharer, as estimated by the Platform. Unlike offline encounters, we roll per
+// shared content rather than per agent.
+pub struct TrustWeighted;
+impl FeedRanker for TrustWeighted {
+    fn feed<'a>(&self, agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        let mut feed = Vec::new();
+        for a_id in platform.following_ids(&agent.id) {
+            // Platforms only have incomplete/noisy information
+            // about "trust" between users, so this uses
+            // the Platform's own estimate of it.
+            let contact_rate = f32::min(1., conf.contact_rate + platform.trust(&agent.id, a_id).unwrap_or(0.));
+
+            let to_share = &share_queues[*a_id];
+            let n_encounters = Binomial::new(
+                to_share.len() as u64,
+                contact_rate as f64).unwrap().sample(rng);
+
+            feed.extend(to_share.choose_multiple(rng, n_encounters as usize));
+        }
+        feed
+    }
+}
+
+fn affinity(agent: &Agent, body: &ContentBody) -> f32 {
+    let sim = similarity(&body.topics, &agent.interests);
+    let align = alignment(&body.values, &agent.values)/2. + 0.5;
+    (sim + align)/2.
+}
+
+// Content closest to the Agent's interests and values,
+// including content from accounts they don't follow.
+// Other accounts are sampled to keep this tractable.
+pub struct ForYou;
+impl FeedRanker for ForYou {
+    fn feed<'a>(&self, agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        let following = platform.following_ids(&agent.id);
+        let others: Vec<AgentId> = platform.users().into_iter()
+            .filter(|a_id| *a_id != agent.id && !following.contains(a_id))
+            .collect();
+        following.iter()
+            .chain(others.choose_multiple(rng, conf.platform.feed_size))
+            .flat_map(|a_id| share_queues[*a_id].iter())
+            .map(|sc| (sc, affinity(agent, &sc.content.body)))
+            .sorted_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap())
+            .take(conf.platform.feed_size)
+            .map(|(sc, _)| sc)
+            .collect()
+    }
+}
+
+// A random selection of followed content
+pub struct RandomFeed;
+impl FeedRanker for RandomFeed {
+    fn feed<'a>(&self, agent: &Agent, platform: &Platform, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        followed_content(agent, platform, share_queues)
+            .choose_multiple(rng, conf.platform.feed_size)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::agent::{Topics, Values};
+    use super::super::content::SharerType;
+    use super::super::testing::{new_body, new_content, new_shared};
+    use rand::SeedableRng;
+
+    fn shared(sharer: AgentId, topics: Vec<f32>) -> SharedContent {
+        let body = ContentBody {
+            topics: Topics::from_vec(topics),
+            ..new_body()
+        };
+        new_shared(new_content(sharer, None, body), (SharerType::Agent, sharer))
+    }
+
+    // Agent 0 follows Agents 1 and 2 but not 3
+    fn setup(ranker: Ranker) -> (Vec<Agent>, Platform, Vec<Vec<SharedContent>>, SimulationConfig, ChaCha20Rng) {
+        let mut conf = SimulationConfig::default();
+        conf.platform.feed_size = 2;
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agents: Vec<Agent> = (0..4).map(|i| Agent::new(i, &conf, &mut rng)).collect();
+        agents[0].interests = Topics::from_vec(vec![1., 1.]);
+        agents[0].values = Values::from_vec(vec![0., 0.]);
+
+        let mut platform = Platform::new(0, ranker);
+        for a in &agents {
+            platform.signup(a.id);
+        }
+        platform.follow(&0, &1);
+        platform.follow(&0, &2);
+
+        let share_queues = vec![
+            vec![],
+            vec![shared(1, vec![0., 0.]), shared(1, vec![0.5, 0.5])],
+            vec![shared(2, vec![0., 1.])],
+            vec![shared(3, vec![1., 1.])],
+        ];
+        (agents, platform, share_queues, conf, rng)
+    }
+
+    #[test]
+    fn chronological() {
+        let (agents, platform, mut share_queues, conf, mut rng) = setup(Ranker::Chronological);
+        let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+        assert_eq!(feed.len(), 2);
+        assert!(feed.iter().all(|sc| sc.sharer.1 != 3));
+
+        // Newer shares come first
+        for sc in &mut share_queues[1] {
+            sc.shared_at = 1;
+        }
+        let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+        assert_eq!(feed[0].content.id, share_queues[1][1].content.id);
+        assert_eq!(feed[1].content.id, share_queues[1][0].content.id);
+    }
+
+    #[test]
+    fn engagement() {
+        let (agents, platform, share_queues, conf, mut rng) = setup(Ranker::Engagement);
+
+        // More shares of this one
+        let popular = share_queues[2][0].content.clone();
+        popular.stats.share();
+        popular.stats.share();
+
+        let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+        assert_eq!(feed.len(), 2);
+        assert_eq!(feed[0].content.id, popular.id);
+    }
+
+    #[test]
+    fn trust_weighted() {
+        let (agents, mut platform, share_queues, conf, mut rng) = setup(Ranker::Trust);
+        for _ in 0..10 {
+            platform.observe(0, 1, true, 0.5, 0., &mut rng);
+            platform.observe(0, 2, false, 0.5, 0., &mut rng);
+            platform.observe(0, 3, true, 0.5, 0., &mut rng);
+        }
+        assert!(platform.trust(&0, &1).unwrap() > 0.99);
+        assert!(platform.trust(&0, &2).unwrap() < 0.01);
+
+        // Only kept for Agents that are followed
+        assert_eq!(platform.trust(&0, &3), None);
+        platform.unfollow(&0, &2);
+        assert_eq!(platform.trust(&0, &2), None);
+        platform.follow(&0, &2);
+
+        // Always sees content from the Agent
+        // the Platform thinks they trust
+        for _ in 0..10 {
+            let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+            assert_eq!(feed.iter().filter(|sc| sc.sharer.1 == 1).count(), 2);
+        }
+    }
+
+    #[test]
+    fn for_you() {
+        let (agents, platform, share_queues, conf, mut rng) = setup(Ranker::Similarity);
+        let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+        assert_eq!(feed.len(), 2);
+
+        // Closest to the Agent's interests,
+        // even though they don't follow the sharer
+        assert_eq!(feed[0].sharer.1, 3);
+        assert_eq!(feed[1].content.id, share_queues[1][1].content.id);
+    }
+
+    #[test]
+    fn random() {
+        let (agents, platform, share_queues, conf, mut rng) = setup(Ranker::Random);
+        let feed = platform.feed(&agents[0], &share_queues, &conf, &mut rng);
+        assert_eq!(feed.len(), 2);
+        assert!(feed.iter().all(|sc| sc.sharer.1 != 3));
+    }
+}
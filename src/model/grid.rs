@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
 
 pub type Position = (isize, isize);
 
@@ -20,6 +21,7 @@ const EVEN_ADJACENT_POSITIONS: [(isize, isize); 6] = [
     (1, 0),   // bottom right
 ];
 
+#[derive(Serialize, Deserialize)]
 pub struct HexGrid {
     pub rows: usize,
     pub cols: usize,
@@ -0,0 +1,441 @@
+use super::agent::{AgentId, Values, Topics};
+use super::util::Vector;
+use super::config::{NetworkFile, SimulationConfig};
+use super::grid::Position;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::{fmt, fs};
+use std::path::Path;
+
+// What's wrong with a network file, and where
+#[derive(Debug, PartialEq)]
+pub struct ImportError {
+    pub file: String,
+
+    // None if it's about the file as a whole
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ImportError {
+    fn at(line: usize, message: String) -> ImportError {
+        ImportError {
+            file: String::new(),
+            line: Some(line),
+            message,
+        }
+    }
+
+    fn in_file(mut self, file: &str) -> ImportError {
+        self.file = file.to_string();
+        self
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+pub type Result<T> = std::result::Result<T, ImportError>;
+
+// What's known about a node
+// besides who it's tied to
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Attributes {
+    pub row: Option<isize>,
+    pub col: Option<isize>,
+    pub values: Option<Values>,
+    pub interests: Option<Topics>,
+}
+
+impl Attributes {
+    pub fn location(&self) -> Option<Position> {
+        match (self.row, self.col) {
+            (Some(r), Some(c)) => Some((r, c)),
+            _ => None
+        }
+    }
+
+    // Attributes go by the same names everywhere:
+    // `row` and `col` for the grid position, which must be
+    // within a `grid_size`-by-`grid_size` grid, and `values`
+    // and `interests` as lists of numbers
+    fn set(&mut self, name: &str, value: &str, grid_size: usize, line: usize) -> Result<()> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+        match name {
+            "row" => self.row = Some(parse_coord(name, value, grid_size, line)?),
+            "col" => self.col = Some(parse_coord(name, value, grid_size, line)?),
+            "values" => self.values = Some(parse_vector(name, value, line)?),
+            "interests" => self.interests = Some(parse_vector(name, value, line)?),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn parse_coord(name: &str, value: &str, grid_size: usize, line: usize) -> Result<isize> {
+    match value.parse::<isize>() {
+        Ok(v) if v >= 0 && (v as usize) < grid_size => Ok(v),
+        Ok(v) => Err(ImportError::at(line, format!("{} {} is outside the {}x{} grid", name, v, grid_size, grid_size))),
+        Err(_) => Err(ImportError::at(line, format!("{} must be an integer, not {:?}", name, value))),
+    }
+}
+
+// Separated by spaces or commas
+fn parse_vector(name: &str, value: &str, line: usize) -> Result<Vector> {
+    let vec = value.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse().map_err(|_| ImportError::at(line, format!("{} must be numbers, not {:?}", name, v))))
+        .collect::<Result<Vec<f32>>>()?;
+    Ok(Vector::from_vec(vec))
+}
+
+// An offline network read from a file.
+// Nodes become Agents in the order they first appear.
+#[derive(Debug, Default)]
+pub struct Graph {
+    // Ids as they are in the file
+    pub ids: Vec<String>,
+    pub attributes: Vec<Attributes>,
+
+    // Who follows whom. Ties that go
+    // both ways appear in both directions.
+    pub edges: Vec<(AgentId, AgentId)>,
+
+    index: FnvHashMap<String, AgentId>,
+    seen: FnvHashSet<(AgentId, AgentId)>,
+}
+
+impl Graph {
+    pub fn n_nodes(&self) -> usize {
+        self.ids.len()
+    }
+
+    // The AgentId for a node, adding it if it's new
+    pub fn node(&mut self, id: &str) -> AgentId {
+        match self.index.get(id) {
+            Some(a_id) => *a_id,
+            None => {
+                let a_id = self.ids.len();
+                self.ids.push(id.to_string());
+                self.attributes.push(Attributes::default());
+                self.index.insert(id.to_string(), a_id);
+                a_id
+            }
+        }
+    }
+
+    // Self-ties and repeated ties are dropped
+    pub fn add_edge(&mut self, source: &str, target: &str, directed: bool) {
+        let a = self.node(source);
+        let b = self.node(target);
+        if a == b {
+            return;
+        }
+        if self.seen.insert((a, b)) {
+            self.edges.push((a, b));
+        }
+        if !directed && self.seen.insert((b, a)) {
+            self.edges.push((b, a));
+        }
+    }
+}
+
+// GraphML files are read as is. Anything else is
+// read as an edge list, along with the node table if given.
+// Locations must be within a `grid_size`-by-`grid_size` grid.
+pub fn load(conf: &NetworkFile, grid_size: usize) -> Result<Graph> {
+    let read = |path: &str| fs::read_to_string(path)
+        .map_err(|err| ImportError {
+            file: path.to_string(),
+            line: None,
+            message: format!("could not read network file: {}", err),
+        });
+
+    let is_graphml = Path::new(&conf.path).extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("graphml"));
+    if is_graphml {
+        parse_graphml(&read(&conf.path)?, grid_size)
+            .map_err(|err| err.in_file(&conf.path))
+    } else {
+        let mut graph = Graph::default();
+        if let Some(nodes) = &conf.nodes {
+            parse_node_table(&read(nodes)?, grid_size, &mut graph)
+                .map_err(|err| err.in_file(nodes))?;
+        }
+        parse_edge_list(&read(&conf.path)?, conf.directed, &mut graph)
+            .map_err(|err| err.in_file(&conf.path))?;
+        Ok(graph)
+    }
+}
+
+// The config's network file, if it has one
+pub fn load_network(conf: &SimulationConfig) -> Option<Graph> {
+    conf.network_file.as_ref()
+        .map(|file| load(file, conf.grid_size).unwrap_or_else(|err| panic!("{}", err)))
+}
+
+// One tie per line, the source and target separated
+// by a comma or whitespace. Anything after is ignored,
+// as are lines starting with `#` and a header
+// starting with `source`.
+pub fn parse_edge_list(text: &str, directed: bool, graph: &mut Graph) -> Result<()> {
+    let mut first = true;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|f| !f.is_empty())
+            .collect();
+        if first {
+            first = false;
+            if fields.first().is_some_and(|f| f.eq_ignore_ascii_case("source")) {
+                continue;
+            }
+        }
+        if fields.len() < 2 {
+            return Err(ImportError::at(i + 1, "expected a source and target".to_string()));
+        }
+        graph.add_edge(fields[0], fields[1], directed);
+    }
+    Ok(())
+}
+
+// CSV with a header naming an `id` column
+// and any of the attribute columns
+pub fn parse_node_table(text: &str, grid_size: usize, graph: &mut Graph) -> Result<()> {
+    let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let (header_line, header): (usize, Vec<&str>) = match lines.next() {
+        Some((i, header)) => (i + 1, header.split(',').map(|h| h.trim()).collect()),
+        None => return Ok(())
+    };
+    let id_col = header.iter().position(|h| *h == "id")
+        .ok_or_else(|| ImportError::at(header_line, "node table needs an id column".to_string()))?;
+    for (i, line) in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        let id = match fields.get(id_col).map(|f| f.trim()) {
+            Some(id) if !id.is_empty() => id,
+            _ => return Err(ImportError::at(i + 1, "expected a node id".to_string()))
+        };
+        let a_id = graph.node(id);
+        for (name, value) in header.iter().zip(fields.iter()) {
+            graph.attributes[a_id].set(name, value, grid_size, i + 1)?;
+        }
+    }
+    Ok(())
+}
+
+// Just enough of GraphML to get nodes, edges
+// and node attributes named as above
+pub fn parse_graphml(text: &str, grid_size: usize) -> Result<Graph> {
+    let mut graph = Graph::default();
+    let mut directed = false;
+    let mut keys: FnvHashMap<String, String> = FnvHashMap::default();
+    let mut node: Option<AgentId> = None;
+
+    for (tag, text) in tags(text)? {
+        let missing = |attr: &str| ImportError::at(tag.line, format!("{} without a {}", tag.name, attr));
+        match (tag.name, tag.end) {
+            ("key", false)
+                if tag.attr("for").is_none_or(|f| f == "node" || f == "all") => {
+                    if let (Some(id), Some(name)) = (tag.attr("id"), tag.attr("attr.name")) {
+                        keys.insert(id.to_string(), name.to_string());
+                    }
+                },
+            ("graph", false) => {
+                directed = tag.attr("edgedefault") == Some("directed");
+            },
+            ("node", false) => {
+                let a_id = graph.node(tag.attr("id").ok_or_else(|| missing("id"))?);
+                if !tag.empty {
+                    node = Some(a_id);
+                }
+            },
+            ("node", true) => {
+                node = None;
+            },
+            ("data", false) => {
+                let name = tag.attr("key").and_then(|k| keys.get(k));
+                if let (Some(a_id), Some(name)) = (node, name) {
+                    graph.attributes[a_id].set(name, &unescape(text), grid_size, tag.line)?;
+                }
+            },
+            ("edge", false) => {
+                let source = tag.attr("source").ok_or_else(|| missing("source"))?;
+                let target = tag.attr("target").ok_or_else(|| missing("target"))?;
+                let directed = match tag.attr("directed") {
+                    Some(d) => d == "true",
+                    None => directed
+                };
+                graph.add_edge(source, target, directed);
+            },
+            _ => {}
+        }
+    }
+    Ok(graph)
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, String)>,
+    line: usize,
+
+    // `</name>` and `<name/>` respectively
+    end: bool,
+    empty: bool,
+}
+
+impl<'a> Tag<'a> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| *k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+// Element tags along with the text that follows each,
+// skipping comments, declarations and the like
+fn tags(text: &str) -> Result<Vec<(Tag<'_>, &str)>> {
+    let mut tags = Vec::new();
+    let mut rest = text;
+    let mut line = 1;
+    while let Some(start) = rest.find('<') {
+        line += rest[..start].matches('\n').count();
+        rest = &rest[start..];
+        let close = if rest.starts_with("<!--") { "-->" } else { ">" };
+        let end = rest.find(close)
+            .ok_or_else(|| ImportError::at(line, "unclosed tag".to_string()))? + close.len();
+        let inner = &rest[1..end-1];
+        let tag_line = line;
+        line += inner.matches('\n').count();
+        rest = &rest[end..];
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        let is_end = inner.starts_with('/');
+        let empty = inner.ends_with('/');
+        let inner = inner.trim_start_matches('/').trim_end_matches('/');
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        tags.push((Tag {
+            name: &inner[..name_end],
+            attrs: parse_attrs(&inner[name_end..], tag_line)?,
+            line: tag_line,
+            end: is_end,
+            empty,
+        }, text));
+    }
+    Ok(tags)
+}
+
+fn parse_attrs(text: &str, line: usize) -> Result<Vec<(&str, String)>> {
+    let mut attrs = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let after = rest[eq+1..].trim_start();
+        let quote = match after.chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => return Err(ImportError::at(line, format!("unquoted attribute {}", key)))
+        };
+        let len = after[1..].find(quote)
+            .ok_or_else(|| ImportError::at(line, format!("unclosed attribute {}", key)))?;
+        attrs.push((key, unescape(&after[1..len+1])));
+        rest = after[len+2..].trim_start();
+    }
+    Ok(attrs)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_list() {
+        let mut graph = Graph::default();
+        parse_node_table("id,row,col,values\nc,1,2,0.5 -0.5\nd,,,\n", 10, &mut graph).unwrap();
+        parse_edge_list("source,target,weight\n# comment\na,b,1\nb c\na,a\nb,a\n", false, &mut graph).unwrap();
+        assert_eq!(graph.ids, vec!["c", "d", "a", "b"]);
+        assert_eq!(graph.edges, vec![(2, 3), (3, 2), (3, 0), (0, 3)]);
+        assert_eq!(graph.attributes[0].location(), Some((1, 2)));
+        assert_eq!(graph.attributes[0].values, Some(Values::from_vec(vec![0.5, -0.5])));
+        assert_eq!(graph.attributes[1], Attributes::default());
+
+        let mut graph = Graph::default();
+        parse_edge_list("a\tb\nb\tc\n", true, &mut graph).unwrap();
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn graphml() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+              <!-- <node id="ignored"/> -->
+              <key id="d0" for="node" attr.name="interests" attr.type="string"/>
+              <key id="d1" for="node" attr.name="row" attr.type="int"/>
+              <key id="d2" for="edge" attr.name="weight" attr.type="double"/>
+              <graph id="G" edgedefault="directed">
+                <node id="n0">
+                  <data key="d0">0.1, 0.9</data>
+                  <data key="d1">3</data>
+                </node>
+                <node id="n1"/>
+                <node id='n&amp;2'></node>
+                <edge source="n0" target="n1"><data key="d2">1.0</data></edge>
+                <edge source="n1" target="n&amp;2" directed="false"/>
+              </graph>
+            </graphml>"#;
+        let graph = parse_graphml(text, 10).unwrap();
+        assert_eq!(graph.ids, vec!["n0", "n1", "n&2"]);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (2, 1)]);
+        assert_eq!(graph.attributes[0].interests, Some(Topics::from_vec(vec![0.1, 0.9])));
+        assert_eq!(graph.attributes[0].row, Some(3));
+        assert_eq!(graph.attributes[0].location(), None);
+    }
+
+    #[test]
+    fn malformed() {
+        let edge_list = |text| parse_edge_list(text, false, &mut Graph::default()).unwrap_err();
+        let node_table = |text| parse_node_table(text, 10, &mut Graph::default()).unwrap_err();
+        assert_eq!(edge_list(",\n").line, Some(1));
+        assert_eq!(edge_list("source,target\na,b\nc\n").line, Some(3));
+        assert_eq!(node_table("row,col\n1,2\n").line, Some(1));
+        assert_eq!(node_table("id,row\na,1\nb,x\n").line, Some(3));
+        assert_eq!(node_table("id,values\n\na,0.5 y\n").line, Some(3));
+        assert_eq!(node_table("id,col\na,10\n").line, Some(2));
+        assert_eq!(node_table("id,row\n,1\n").line, Some(2));
+
+        let err = parse_graphml("<graph>\n  <node id='a'/>\n  <edge source='a'/>\n</graph>", 10).unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert_eq!(parse_graphml("<graph>\n<node id=a/>", 10).unwrap_err().line, Some(2));
+        assert_eq!(parse_graphml("<graph\n", 10).unwrap_err().line, Some(1));
+
+        let conf = NetworkFile {
+            path: "does/not/exist.csv".to_string(),
+            nodes: None,
+            directed: false,
+        };
+        let err = load(&conf, 10).unwrap_err();
+        assert_eq!(err.file, "does/not/exist.csv");
+        assert_eq!(err.line, None);
+        assert!(err.to_string().starts_with("does/not/exist.csv: "));
+    }
+}
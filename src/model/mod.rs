@@ -4,40 +4,118 @@ mod grid;
 mod agent;
 mod policy;
 mod content;
+mod cascade;
 mod network;
+pub mod import;
 mod platform;
+mod feed;
+mod moderation;
+mod fact_checker;
 mod publisher;
 mod motive;
 mod config;
+mod builder;
+pub mod snapshot;
+
+pub use self::policy::{Policy, PolicyId, PolicyRecord, Undo};
+pub use self::sim::{Simulation, MarketEvent};
+pub use self::builder::SimulationBuilder;
+pub use self::agent::{Agent, Values, Topics, AgentId};
+pub use self::config::{SimulationConfig, PublisherConfig, SinglePublisherConfig, PlatformConfig, SinglePlatformConfig, AgentConfig, FactCheckerConfig, BotConfig, NetworkFile};
+pub use self::publisher::{Publisher, PublisherId, Audience, Pricing};
+pub use self::feed::{FeedRanker, Ranker};
+pub use self::moderation::{Rule, Flag, ModerationStats};
+pub use self::fact_checker::{FactChecker, FactCheckerId};
+pub use self::platform::{Platform, PlatformId};
+pub use self::content::{Content, ContentId, ContentBody, ContentStats, ContentExpiry, ExpiredContent, SharedContent, SharerType, Label, Verdict};
+pub use self::cascade::{Cascade, Channel, ShareEvent, ExpiredCascade};
+pub use self::network::Network;
+pub use self::import::{Graph, Attributes, ImportError};
+pub use self::motive::Motive;
+pub use self::grid::{HexGrid, Position};
+
+// Fixtures shared by tests across the model
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
 
-pub use self::policy::Policy;
-pub use self::sim::Simulation;
-pub use self::agent::{Agent, Values, AgentId};
-pub use self::config::SimulationConfig;
-pub use self::publisher::{Publisher, PublisherId};
-pub use self::grid::Position;
+    // What most Simulation tests start from
+    pub fn test_conf() -> SimulationConfig {
+        let mut conf = SimulationConfig::default();
+        conf.population = 200;
+        conf
+    }
+
+    // Seeded the same way every time, along
+    // with the RNG to keep stepping it with
+    pub fn test_sim(conf: &SimulationConfig) -> (Simulation, ChaCha20Rng) {
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let sim = Simulation::new(conf, &mut rng);
+        (sim, rng)
+    }
 
+    // Neutral in every way, for tests to
+    // override what they care about
+    pub fn new_body() -> ContentBody {
+        ContentBody {
+            topics: Topics::from_vec(vec![0., 0.]),
+            values: Values::from_vec(vec![0., 0.]),
+            cost: 1.,
+            depth: 1.,
+            spectacle: 1.,
+            veracity: 1.,
+        }
+    }
+
+    // Content with no ads, paywall or label
+    pub fn new_content(author: AgentId, publisher: Option<PublisherId>, body: ContentBody) -> Content {
+        Content {
+            id: ContentId::new_v4(),
+            publisher,
+            author,
+            body,
+            ads: 0.,
+            stats: ContentStats::default(),
+            paywalled: false,
+            label: Label::default(),
+        }
+    }
+
+    // Content as first shared, by its author or publisher
+    pub fn new_shared(content: impl Into<Arc<Content>>, sharer: (SharerType, usize)) -> SharedContent {
+        SharedContent {
+            content: content.into(),
+            sharer,
+            chain: Vec::new(),
+            shared_at: 0,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::testing::{new_content, new_shared};
     use super::grid::HexGrid;
     use super::agent::Topics;
     use super::platform::{Platform, PlatformId};
-    use super::config::{AgentConfig, PublisherConfig};
-    use super::content::{Content, ContentId, ContentBody, SharedContent, SharerType};
+    use super::config::AgentConfig;
+    use super::content::{Content, ContentBody, SharedContent, SharerType, Verdict};
     use self::publisher::Audience;
     use super::sim::{compute_distances, set_agent_relevancies, ad_market};
     use super::util::Vector;
-    use rand::rngs::StdRng;
+    use rand_chacha::ChaCha20Rng;
     use rand::SeedableRng;
     use rand::seq::SliceRandom;
     use std::sync::Arc;
-    use fnv::FnvHashMap;
+    use fnv::{FnvHashMap, FnvHashSet};
 
-    fn standard_agents(conf: &SimulationConfig, rng: &mut StdRng) -> Vec<Agent> {
+    fn standard_agents(conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<Agent> {
         (0..100).map(|i| {
-            let mut agent = Agent::new(i, &conf.agent, rng);
+            let mut agent = Agent::new(i, conf, rng);
             agent.values = Values::from_vec(vec![0., 0.]);
             agent.interests = Topics::from_vec(vec![1., 1.]);
             agent.attention = 20.;
@@ -53,18 +131,19 @@ mod tests {
         let trust = 1.;
         let gravity_stretch = 10.;
         let max_influence = 0.1;
-        let conf = AgentConfig {
+        let mut conf = SimulationConfig::default();
+        conf.agent = AgentConfig {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumer = Agent::new(0, &conf, &mut rng);
         consumer.values = Values::from_vec(vec![0., 0.]);
         let mut producer = Agent::new(1, &conf, &mut rng);
         producer.values = Values::from_vec(vec![-1., -1.]);
 
         for _ in 0..200 {
-            let body = producer.produce(conf.attention_budget, &mut rng);
+            let body = producer.produce(conf.agent.attention_budget, &mut rng);
             consumer.be_influenced(&body.values, gravity_stretch, max_influence, trust);
         }
 
@@ -78,7 +157,7 @@ mod tests {
 
         producer.values = Values::from_vec(vec![1., 1.]);
         for _ in 0..500 {
-            let body = producer.produce(conf.attention_budget, &mut rng);
+            let body = producer.produce(conf.agent.attention_budget, &mut rng);
             consumer.be_influenced(&body.values, gravity_stretch, max_influence, trust);
         }
 
@@ -94,7 +173,7 @@ mod tests {
     #[test]
     fn producers_produce_aligned_content() {
         let conf = SimulationConfig::default();
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
 
         let trials = 100;
         let max_distance = 0.1;
@@ -106,12 +185,12 @@ mod tests {
         ];
         for v in values {
             let mut count = 0;
-            let mut producer = Agent::new(0, &conf.agent, &mut rng);
+            let mut producer = Agent::new(0, &conf, &mut rng);
             producer.values = Values::from_vec(v);
             for _ in 0..trials {
                 let body = producer.produce(conf.agent.attention_budget, &mut rng);
                 let values = body.values;
-                let p_vals = producer.values;
+                let p_vals = &producer.values;
                 if (p_vals[0] - values[0]).abs() <= max_distance && (p_vals[1] - values[1]).abs() <= max_distance {
                     count += 1;
                 }
@@ -138,9 +217,9 @@ mod tests {
             vec![ 0.25,  0.25],
             vec![ 0.25, -0.25],
         ];
-        let mut rng: StdRng = SeedableRng::seed_from_u64(1);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(1);
         let mut consumers: Vec<Agent> = (0..4).map(|i| {
-            let mut agent = Agent::new(i, &conf.agent, &mut rng);
+            let mut agent = Agent::new(i, &conf, &mut rng);
             agent.values = Values::from_vec(center_values[i].clone());
             agent
         }).collect();
@@ -155,7 +234,7 @@ mod tests {
         for i in 0..10 {
             for j in 0..4 {
                 let id =  consumers.len() + (i * 4) + j;
-                let mut producer = Agent::new(id, &conf.agent, &mut rng);
+                let mut producer = Agent::new(id, &conf, &mut rng);
                 producer.values = Values::from_vec(values[j].clone());
                 producers.push(producer);
             }
@@ -173,24 +252,15 @@ mod tests {
                 body.depth = 1.;
                 body.spectacle = 1.;
 
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: p.id,
-                    body: body,
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, p.id)
-                }
+                let content = new_content(p.id, None, body);
+                new_shared(content, (SharerType::Agent, p.id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                a.consume(&shared, &conf, &mut rng);
+                a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
             }
         }
 
@@ -199,7 +269,7 @@ mod tests {
         let max_distance = 0.15;
         for i in 0..4 {
             let agent = &consumers[i];
-            let a_vals = agent.values;
+            let a_vals = &agent.values;
             let values = &values[i];
             // println!("{:?}", a_vals);
             assert!((a_vals[0] - values[0]).abs() <= max_distance && (a_vals[1] - values[1]).abs() <= max_distance);
@@ -213,7 +283,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.media_literacy = 1.;
@@ -227,31 +297,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        // High quality vs low quality
-                        depth: if i < 50 {low} else {high},
-                        spectacle: 1.,
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    depth: if i < 50 {low} else {high},
+                    spectacle: 1.,
+                    veracity: 1.,
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.depth == high {
                         high_quality_shares += 1;
@@ -272,7 +333,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.media_literacy = 0.;
@@ -286,31 +347,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        // High quality vs low quality
-                        depth: if i < 50 {low} else {high},
-                        spectacle: 1.,
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    depth: if i < 50 {low} else {high},
+                    spectacle: 1.,
+                    veracity: 1.,
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.depth == high {
                         high_quality_shares += 1;
@@ -331,7 +383,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.media_literacy = 1.;
@@ -345,31 +397,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        // High quality vs low quality
-                        spectacle: if i < 50 {low} else {high},
-                        depth: 1.,
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    spectacle: if i < 50 {low} else {high},
+                    veracity: 1.,
+                    depth: 1.,
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.spectacle == high {
                         high_quality_shares += 1;
@@ -390,7 +433,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.media_literacy = 0.;
@@ -404,31 +447,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        // High quality vs low quality
-                        spectacle: if i < 50 {low} else {high},
-                        depth: 1.,
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    spectacle: if i < 50 {low} else {high},
+                    veracity: 1.,
+                    depth: 1.,
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.spectacle == high {
                         high_quality_shares += 1;
@@ -449,7 +483,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
 
         let low = 0.1;
@@ -460,32 +494,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        // High attention vs low attention
-                        cost: if i < 50 {low} else {high},
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    cost: if i < 50 {low} else {high},
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.cost == high {
                         high_attention_shares += 1;
@@ -506,7 +530,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
 
         let aligned = Values::from_vec(vec![0., 0.]);
@@ -517,31 +541,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: if i < 50 {aligned.clone()} else {not_aligned.clone()},
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: if i < 50 {aligned.clone()} else {not_aligned.clone()},
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.values == aligned {
                         aligned_shares += 1;
@@ -562,7 +577,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
 
         let aligned = Topics::from_vec(vec![1., 1.]);
@@ -573,31 +588,22 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        topics: if i < 50 {aligned.clone()} else {not_aligned.clone()},
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, None, ContentBody {
+                    topics: if i < 50 {aligned.clone()} else {not_aligned.clone()},
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     if shared.body.topics == aligned {
                         aligned_shares += 1;
@@ -617,26 +623,24 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
 
         let near_id = 0;
-        let mut publisher_near = Publisher::new(near_id, &conf.publisher, &mut rng);
+        let mut publisher_near = Publisher::new(near_id, &conf, &mut rng);
         publisher_near.location = (0, 0);
         publisher_near.radius = 1;
 
         let medium_id = 1;
-        let mut publisher_medium = Publisher::new(medium_id, &conf.publisher, &mut rng);
+        let mut publisher_medium = Publisher::new(medium_id, &conf, &mut rng);
         publisher_medium.location = (2, 2);
         publisher_medium.radius = 2;
 
         let far_id = 2;
-        let mut publisher_far = Publisher::new(far_id, &conf.publisher, &mut rng);
+        let mut publisher_far = Publisher::new(far_id, &conf, &mut rng);
         publisher_far.location = (6, 6);
         publisher_far.radius = 1;
 
@@ -656,37 +660,28 @@ mod tests {
         let author_id = consumers.len();
         for _ in 0..10 {
             let content: Vec<SharedContent> = (0..120).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 40 {
-                        near_id
-                    } else if i < 80 {
-                        medium_id
-                    } else {
-                        far_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, Some(if i < 40 {
+                    near_id
+                } else if i < 80 {
+                    medium_id
+                } else {
+                    far_id
+                }), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let will_share = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for shared in will_share {
                     match shared.publisher {
                         Some(publisher) => {
@@ -716,9 +711,9 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut rich: Vec<Agent> = (0..100).map(|i| {
-            let mut agent = Agent::new(i, &conf.agent, &mut rng);
+            let mut agent = Agent::new(i, &conf, &mut rng);
             agent.resources = 100.;
 
             // Control for quality and reach
@@ -730,7 +725,7 @@ mod tests {
             agent
         }).collect();
         let mut poor: Vec<Agent> = (0..100).map(|i| {
-            let mut agent = Agent::new(rich.len() + i, &conf.agent, &mut rng);
+            let mut agent = Agent::new(rich.len() + i, &conf, &mut rng);
             agent.resources = 5.;
 
             // Control for quality
@@ -773,13 +768,13 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
 
-        let mut agent_a = Agent::new(0, &conf.agent, &mut rng);
+        let mut agent_a = Agent::new(0, &conf, &mut rng);
         agent_a.values = Values::from_vec(vec![-1., -1.]);
         agent_a.interests = Topics::from_vec(vec![1., 1.]);
 
-        let mut agent_b = Agent::new(1, &conf.agent, &mut rng);
+        let mut agent_b = Agent::new(1, &conf, &mut rng);
         agent_b.values = Values::from_vec(vec![ 1.,  1.]);
         agent_b.interests = Topics::from_vec(vec![1., 1.]);
 
@@ -788,51 +783,42 @@ mod tests {
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
                 let author_id = if i < 50 {producer_a} else {producer_b};
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: if i < 50 {
-                            Values::from_vec(vec![-1., -1.])
-                        } else {
-                            Values::from_vec(vec![ 1.,  1.])
-                        },
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                let content = new_content(author_id, None, ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: if i < 50 {
+                        Values::from_vec(vec![-1., -1.])
+                    } else {
+                        Values::from_vec(vec![ 1.,  1.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent_a.consume(&shared, &conf, &mut rng);
+            agent_a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
             shared.shuffle(&mut rng);
-            agent_b.consume(&shared, &conf, &mut rng);
+            agent_b.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust_a = agent_a.trust;
-        let trust_a__a = trust_a.get(&producer_a).unwrap();
-        let trust_a__b = trust_a.get(&producer_b).unwrap();
-        println!("a trust of p_a:{:?} p_b:{:?}", trust_a__a, trust_a__b);
-        assert!(*trust_a__a > 0.8);
-        assert_eq!(*trust_a__b, 0.0);
+        let trust_a_a = trust_a.get(&producer_a).unwrap();
+        let trust_a_b = trust_a.get(&producer_b).unwrap();
+        println!("a trust of p_a:{:?} p_b:{:?}", trust_a_a, trust_a_b);
+        assert!(*trust_a_a > 0.8);
+        assert_eq!(*trust_a_b, 0.0);
 
         let trust_b = agent_b.trust;
-        let trust_b__a = trust_b.get(&producer_a).unwrap();
-        let trust_b__b = trust_b.get(&producer_b).unwrap();
-        println!("b trust of p_a:{:?} p_b:{:?}", trust_b__a, trust_b__b);
-        assert_eq!(*trust_b__a, 0.0);
-        assert!(*trust_b__b > 0.8);
+        let trust_b_a = trust_b.get(&producer_a).unwrap();
+        let trust_b_b = trust_b.get(&producer_b).unwrap();
+        println!("b trust of p_a:{:?} p_b:{:?}", trust_b_a, trust_b_b);
+        assert_eq!(*trust_b_a, 0.0);
+        assert!(*trust_b_b > 0.8);
     }
 
     #[test]
@@ -843,12 +829,12 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
 
-        let mut agent_a = Agent::new(0, &conf.agent, &mut rng);
+        let mut agent_a = Agent::new(0, &conf, &mut rng);
         agent_a.interests = Topics::from_vec(vec![ 0., 1.]);
 
-        let mut agent_b = Agent::new(1, &conf.agent, &mut rng);
+        let mut agent_b = Agent::new(1, &conf, &mut rng);
         agent_b.interests = Topics::from_vec(vec![ 1., 0.]);
 
         let producer_a = 2;
@@ -856,36 +842,27 @@ mod tests {
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
                 let author_id = if i < 50 {producer_a} else {producer_b};
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: None,
-                    author: author_id,
-                    body: ContentBody {
-                        topics: if i < 50 {
-                            Topics::from_vec(vec![ 0., 1.])
-                        } else {
-                            Topics::from_vec(vec![ 1., 0.])
-                        },
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                let content = new_content(author_id, None, ContentBody {
+                    topics: if i < 50 {
+                        Topics::from_vec(vec![ 0., 1.])
+                    } else {
+                        Topics::from_vec(vec![ 1., 0.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent_a.consume(&shared, &conf, &mut rng);
+            agent_a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
             shared.shuffle(&mut rng);
-            agent_b.consume(&shared, &conf, &mut rng);
+            agent_b.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust_a = agent_a.trust;
@@ -905,13 +882,11 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-        let mut agent = Agent::new(0, &conf.agent, &mut rng);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &conf, &mut rng);
         agent.values = Values::from_vec(vec![0., 0.]);
         agent.interests = Topics::from_vec(vec![1., 1.]);
         agent.location = (0, 0);
@@ -920,50 +895,41 @@ mod tests {
 
         // Control for location
         let pub_a_id = 0;
-        let mut publisher_a = Publisher::new(pub_a_id, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(pub_a_id, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let pub_b_id = 1;
-        let mut publisher_b = Publisher::new(pub_b_id, &conf.publisher, &mut rng);
+        let mut publisher_b = Publisher::new(pub_b_id, &conf, &mut rng);
         publisher_b.location = (0, 0);
         publisher_b.radius = 1;
 
         let author_id = 1;
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
+                let content = new_content(author_id, Some(if i < 50 {
+                    pub_a_id
+                } else {
+                    pub_b_id
+                }), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: if i < 50 {
+                        Values::from_vec(vec![0., 0.])
                     } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: if i < 50 {
-                            Values::from_vec(vec![0., 0.])
-                        } else {
-                            Values::from_vec(vec![-1., -1.])
-                        },
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                        Values::from_vec(vec![-1., -1.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent.consume(&shared, &conf, &mut rng);
+            agent.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust = agent.publishers;
@@ -980,13 +946,11 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-        let mut agent = Agent::new(0, &conf.agent, &mut rng);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &conf, &mut rng);
         agent.values = Values::from_vec(vec![0., 0.]);
         agent.interests = Topics::from_vec(vec![1., 1.]);
         agent.location = (0, 0);
@@ -995,50 +959,41 @@ mod tests {
 
         // Control for location
         let pub_a_id = 0;
-        let mut publisher_a = Publisher::new(pub_a_id, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(pub_a_id, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let pub_b_id = 1;
-        let mut publisher_b = Publisher::new(pub_b_id, &conf.publisher, &mut rng);
+        let mut publisher_b = Publisher::new(pub_b_id, &conf, &mut rng);
         publisher_b.location = (0, 0);
         publisher_b.radius = 1;
 
         let author_id = 1;
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
+                let content = new_content(author_id, Some(if i < 50 {
+                    pub_a_id
+                } else {
+                    pub_b_id
+                }), ContentBody {
+                    topics: if i < 50 {
+                        Topics::from_vec(vec![1., 1.])
                     } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: if i < 50 {
-                            Topics::from_vec(vec![1., 1.])
-                        } else {
-                            Topics::from_vec(vec![0., 0.])
-                        },
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                        Topics::from_vec(vec![0., 0.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent.consume(&shared, &conf, &mut rng);
+            agent.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust = agent.publishers;
@@ -1055,13 +1010,11 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-        let mut agent = Agent::new(0, &conf.agent, &mut rng);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &conf, &mut rng);
         agent.values = Values::from_vec(vec![0., 0.]);
         agent.interests = Topics::from_vec(vec![1., 1.]);
         agent.location = (0, 0);
@@ -1070,46 +1023,37 @@ mod tests {
 
         // Control for location
         let pub_a_id = 0;
-        let mut publisher_a = Publisher::new(pub_a_id, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(pub_a_id, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let pub_b_id = 1;
-        let mut publisher_b = Publisher::new(pub_b_id, &conf.publisher, &mut rng);
+        let mut publisher_b = Publisher::new(pub_b_id, &conf, &mut rng);
         publisher_b.location = (0, 0);
         publisher_b.radius = 1;
 
         let author_id = 1;
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
-                    } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = new_content(author_id, Some(if i < 50 {
+                    pub_a_id
+                } else {
+                    pub_b_id
+                }), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent.consume(&shared, &conf, &mut rng);
+            agent.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust = agent.publishers;
@@ -1126,13 +1070,11 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-        let mut agent = Agent::new(0, &conf.agent, &mut rng);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &conf, &mut rng);
         agent.values = Values::from_vec(vec![0., 0.]);
         agent.interests = Topics::from_vec(vec![1., 1.]);
         agent.location = (0, 0);
@@ -1141,50 +1083,38 @@ mod tests {
 
         // Control for location
         let pub_a_id = 0;
-        let mut publisher_a = Publisher::new(pub_a_id, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(pub_a_id, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let pub_b_id = 1;
-        let mut publisher_b = Publisher::new(pub_b_id, &conf.publisher, &mut rng);
+        let mut publisher_b = Publisher::new(pub_b_id, &conf, &mut rng);
         publisher_b.location = (0, 0);
         publisher_b.radius = 1;
 
         let author_id = 1;
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
-                    } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: if i < 50 {
-                        0.
-                    } else {
-                        10.
-                    }
+                let (publisher, ads) = if i < 50 {
+                    (pub_a_id, 0.)
+                } else {
+                    (pub_b_id, 10.)
                 };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                let content = Content { ads, ..new_content(author_id, Some(publisher), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                }) };
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             shared.shuffle(&mut rng);
-            agent.consume(&shared, &conf, &mut rng);
+            agent.consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
         }
 
         let trust = agent.publishers;
@@ -1201,18 +1131,16 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
 
         // Dummy
-        let mut author = Agent::new(0, &conf.agent, &mut rng);
+        let mut author = Agent::new(0, &conf, &mut rng);
 
-        let mut publisher = Publisher::new(0, &conf.publisher, &mut rng);
-        let mut audience = Audience::new(&mut rng);
+        let mut publisher = Publisher::new(0, &conf, &mut rng);
+        let mut audience = Audience::new(&conf, &mut rng);
         let var = Vector::from_vec(vec![0.5, 0.5]);
         audience.values = (Values::from_vec(vec![1., 1.]), var.clone());
         audience.interests = (Topics::from_vec(vec![0., 1.]), var.clone());
@@ -1227,6 +1155,7 @@ mod tests {
                     // quality: 0., // So it costs nothing
                     depth: 0.,
                     spectacle: 0.,
+                    veracity: 1.,
                     topics: Topics::from_vec(vec![0., 1.]),
                     values: Values::from_vec(vec![1., 1.]),
                     cost: 1.,
@@ -1237,6 +1166,7 @@ mod tests {
                     // quality: 0., // So it costs nothing
                     depth: 0.,
                     spectacle: 0.,
+                    veracity: 1.,
                     topics: Topics::from_vec(vec![0.5, 0.5]),
                     values: Values::from_vec(vec![0., 0.]),
                     cost: 1.,
@@ -1247,6 +1177,7 @@ mod tests {
                     // quality: 0., // So it costs nothing
                     depth: 0.,
                     spectacle: 0.,
+                    veracity: 1.,
                     topics: Topics::from_vec(vec![ 1.,  0.]),
                     values: Values::from_vec(vec![-1., -1.]),
                     cost: 1.,
@@ -1283,20 +1214,18 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
 
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.relevancies.push(1.0);
         }
 
-        let mut publisher = Publisher::new(0, &conf.publisher, &mut rng);
-        let mut audience = Audience::new(&mut rng);
+        let mut publisher = Publisher::new(0, &conf, &mut rng);
+        let mut audience = Audience::new(&conf, &mut rng);
         let var = Vector::from_vec(vec![0.5, 0.5]);
         audience.values = (Values::from_vec(vec![-1., -1.]), var.clone());
         audience.interests = (Topics::from_vec(vec![0., 0.]), var.clone());
@@ -1307,28 +1236,19 @@ mod tests {
         let ints_expected = Topics::from_vec(vec![1., 1.]);
         let mut shared: Vec<SharedContent> = Vec::new();
         for _ in 0..10 {
-            for i in 0..10 {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(publisher.id),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: ints_expected.clone(),
-                        values: vals_expected.clone(),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
+            for _ in 0..10 {
+                let content = new_content(author_id, Some(publisher.id), ContentBody {
+                    topics: ints_expected.clone(),
+                    values: vals_expected.clone(),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
                 let content = Arc::new(content);
                 publisher.content.push(content.clone());
 
-                let to_share = SharedContent {
-                    content: content.clone(),
-                    sharer: (SharerType::Agent, author_id)
-                };
+                let to_share = new_shared(content.clone(), (SharerType::Agent, author_id));
                 shared.push(to_share);
             }
 
@@ -1336,12 +1256,9 @@ mod tests {
                 let mut s: Vec<(Option<&PlatformId>, &SharedContent)> = shared.iter()
                     .map(|c| (None, c)).collect();
                 s.shuffle(&mut rng);
-                let (will_share, _, _, _, _) = a.consume(&s, &conf, &mut rng);
+                let will_share = a.consume(&s, &FnvHashSet::default(), &conf, &mut rng).to_share;
                 for c in will_share {
-                    let to_share = SharedContent {
-                        content: c.clone(),
-                        sharer: (SharerType::Agent, a.id)
-                    };
+                    let to_share = new_shared(c.clone(), (SharerType::Agent, a.id));
                     shared.push(to_share);
                 }
             }
@@ -1363,12 +1280,10 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             // Equally relevant
@@ -1378,12 +1293,12 @@ mod tests {
 
         // Control for location
         let pub_a_id = 0;
-        let mut publisher_a = Publisher::new(pub_a_id, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(pub_a_id, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let pub_b_id = 1;
-        let mut publisher_b = Publisher::new(pub_b_id, &conf.publisher, &mut rng);
+        let mut publisher_b = Publisher::new(pub_b_id, &conf, &mut rng);
         publisher_b.location = (0, 0);
         publisher_b.radius = 1;
 
@@ -1391,36 +1306,27 @@ mod tests {
         let mut subs: Vec<isize> = vec![0, 0];
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
+                let content = new_content(author_id, Some(if i < 50 {
+                    pub_a_id
+                } else {
+                    pub_b_id
+                }), ContentBody {
+                    topics: if i < 50 {
+                        Topics::from_vec(vec![1., 1.])
                     } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: if i < 50 {
-                            Topics::from_vec(vec![1., 1.])
-                        } else {
-                            Topics::from_vec(vec![0., 0.])
-                        },
-                        values: if i < 50 {
-                            Values::from_vec(vec![0., 0.])
-                        } else {
-                            Values::from_vec(vec![-1., -1.])
-                        },
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                        Topics::from_vec(vec![0., 0.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    values: if i < 50 {
+                        Values::from_vec(vec![0., 0.])
+                    } else {
+                        Values::from_vec(vec![-1., -1.])
+                    },
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
 
@@ -1428,7 +1334,7 @@ mod tests {
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, (new_subs, unsubs), _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let (new_subs, unsubs) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).subs;
                 for pub_id in new_subs {
                     subs[pub_id] += 1;
                 }
@@ -1446,36 +1352,27 @@ mod tests {
         // Unsubscribe as trust declines
         for _ in 0..100 {
             let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(if i < 50 {
-                        pub_a_id
+                let content = new_content(author_id, Some(if i < 50 {
+                    pub_a_id
+                } else {
+                    pub_b_id
+                }), ContentBody {
+                    topics: if i < 50 {
+                        Topics::from_vec(vec![0., 0.])
                     } else {
-                        pub_b_id
-                    }),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: if i < 50 {
-                            Topics::from_vec(vec![0., 0.])
-                        } else {
-                            Topics::from_vec(vec![1., 1.])
-                        },
-                        values: if i < 50 {
-                            Values::from_vec(vec![-1., -1.])
-                        } else {
-                            Values::from_vec(vec![0., 0.])
-                        },
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
+                        Topics::from_vec(vec![1., 1.])
                     },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+                    values: if i < 50 {
+                        Values::from_vec(vec![-1., -1.])
+                    } else {
+                        Values::from_vec(vec![0., 0.])
+                    },
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
 
@@ -1483,7 +1380,7 @@ mod tests {
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, (new_subs, unsubs), _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let (new_subs, unsubs) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).subs;
                 for pub_id in new_subs {
                     subs[pub_id] += 1;
                 }
@@ -1505,51 +1402,40 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.relevancies.push(1.0);
         }
 
         // Control for location
-        let mut publisher_a = Publisher::new(0, &conf.publisher, &mut rng);
+        let mut publisher_a = Publisher::new(0, &conf, &mut rng);
         publisher_a.location = (0, 0);
         publisher_a.radius = 1;
 
         let author_id = consumers.len();
         let mut subs: Vec<isize> = vec![0];
         for _ in 0..10 {
-            let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(0),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+            let content: Vec<SharedContent> = (0..100).map(|_| {
+                let content = new_content(author_id, Some(0), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, (new_subs, unsubs), _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let (new_subs, unsubs) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).subs;
                 for pub_id in new_subs {
                     subs[pub_id] += 1;
                 }
@@ -1563,7 +1449,7 @@ mod tests {
         let shared = vec![];
         for _ in 0..100 {
             for a in &mut consumers {
-                let (_, (new_subs, unsubs), _, _, _) = a.consume(&shared, &conf, &mut rng);
+                let (new_subs, unsubs) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).subs;
                 for pub_id in new_subs {
                     subs[pub_id] += 1;
                 }
@@ -1582,56 +1468,104 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.relevancies.push(1.0);
         }
 
         // Control for location
-        let mut publisher = Publisher::new(0, &conf.publisher, &mut rng);
+        let mut publisher = Publisher::new(0, &conf, &mut rng);
         publisher.location = (0, 0);
         publisher.radius = 1;
 
         let mut ad_revenue = 0.;
         let author_id = consumers.len();
         for _ in 0..10 {
-            let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(0),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 1.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, author_id)
-                }
+            let content: Vec<SharedContent> = (0..100).map(|_| {
+                let content = Content { ads: 1., ..new_content(author_id, Some(0), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                }) };
+                new_shared(content, (SharerType::Agent, author_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, _, _, _, revenue) = a.consume(&shared, &conf, &mut rng);
+                let revenue = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).revenue;
                 ad_revenue += revenue.get(&(SharerType::Publisher, publisher.id)).unwrap();
             }
         }
+        // Each consumer reads 20 pieces per round
         println!("ad revenue:{:?}", ad_revenue);
-        assert_eq!(ad_revenue, 1. * 1. * 10. * 100.);
+        assert_eq!(ad_revenue, 1. * 1. * 10. * 100. * 20.);
+    }
+
+    #[test]
+    fn platform_ad_cut() {
+        let mut conf = SimulationConfig::default();
+        conf.revenue_per_ad = 1.;
+        conf.platform.ad_cut = 0.3;
+
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut consumers = standard_agents(&conf, &mut rng);
+        for a in &mut consumers {
+            a.relevancies.push(1.0);
+        }
+
+        let author_id = consumers.len();
+        let content: Vec<SharedContent> = (0..2).map(|_| {
+            let content = Content { ads: 1., ..new_content(author_id, Some(0), ContentBody {
+                topics: Topics::from_vec(vec![1., 1.]),
+                values: Values::from_vec(vec![0., 0.]),
+                cost: 1.,
+                depth: 1.,
+                spectacle: 1.,
+                veracity: 1.,
+            }) };
+            new_shared(content, (SharerType::Agent, author_id))
+        }).collect();
+
+        // Read on a Platform, the Platform takes its cut
+        let p_id = 0;
+        let shared: Vec<(Option<&PlatformId>, &SharedContent)> = vec![(Some(&p_id), &content[0])];
+        let consumed = consumers[0].consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
+        let revenue = consumed.revenue;
+        let platform_revenue = consumed.platform_revenue;
+        assert_eq!(revenue[&(SharerType::Publisher, 0)], 0.7);
+        assert_eq!(platform_revenue[&p_id], 0.3);
+
+        // Read offline, it doesn't
+        let shared: Vec<(Option<&PlatformId>, &SharedContent)> = vec![(None, &content[1])];
+        let consumed = consumers[1].consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
+        let revenue = consumed.revenue;
+        let platform_revenue = consumed.platform_revenue;
+        assert_eq!(revenue[&(SharerType::Publisher, 0)], 1.);
+        assert!(platform_revenue.is_empty());
+
+        // Every piece from the same Publisher is paid for,
+        // so all the ad revenue is accounted for
+        let shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter().map(|sc| (Some(&p_id), sc)).collect();
+        let consumed = consumers[2].consume(&shared, &FnvHashSet::default(), &conf, &mut rng);
+        let total = consumed.revenue[&(SharerType::Publisher, 0)] + consumed.platform_revenue[&p_id];
+        assert!((consumed.revenue[&(SharerType::Publisher, 0)] - 1.4).abs() < 1e-6);
+        assert!((total - 2.).abs() < 1e-6);
+
+        let mut platform = Platform::new(p_id, Ranker::Trust);
+        platform.conversion_rate = 0.5;
+        platform.earn(10., 0.3, conf.revenue_per_ad, 1.);
+        assert_eq!(platform.ad_revenue, 0.3);
+        assert_eq!(platform.revenue, 5.3);
+        assert_eq!(platform.budget, 4.3);
     }
 
     #[test]
@@ -1643,12 +1577,10 @@ mod tests {
         conf.agent = AgentConfig {
             attention_budget: 20.
         };
-        conf.publisher = PublisherConfig {
-            revenue_per_subscriber: 10.,
-            base_budget: 10000.
-        };
+        conf.publisher.revenue_per_subscriber = 10.;
+        conf.publisher.base_budget = 10000.;
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.relevancies.push(1.0);
@@ -1656,7 +1588,7 @@ mod tests {
 
         // Control for location
         let pub_id = 0;
-        let mut publisher = Publisher::new(pub_id, &conf.publisher, &mut rng);
+        let mut publisher = Publisher::new(pub_id, &conf, &mut rng);
         publisher.location = (0, 0);
         publisher.radius = 1;
         publisher.ads = 10.;
@@ -1667,21 +1599,15 @@ mod tests {
         let mut ad_revenue = 0.;
         let author_id = consumers.len();
         for _ in 0..10 {
-            let content: Vec<Content> = (0..100).map(|i| {
-                Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(pub_id),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                }
+            let content: Vec<Content> = (0..100).map(|_| {
+                new_content(author_id, Some(pub_id), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                })
             }).collect();
 
             let mut c: FnvHashMap<(SharerType, usize), Vec<Content>> = FnvHashMap::default();
@@ -1690,23 +1616,20 @@ mod tests {
             ad_market(&mut c, &consumers, &publishers, &platforms, &conf, &mut rng);
 
             let content = c.remove(&(SharerType::Publisher, pub_id)).unwrap();
-            let shared_content: Vec<SharedContent> = content.into_iter().map(|c| SharedContent {
-                content: Arc::new(c),
-                sharer: (SharerType::Agent, author_id)
-            }).collect();
+            let shared_content: Vec<SharedContent> = content.into_iter().map(|c| new_shared(c, (SharerType::Agent, author_id))).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = shared_content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, _, _, _, revenue) = a.consume(&shared, &conf, &mut rng);
+                let revenue = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).revenue;
                 ad_revenue += revenue.get(&(SharerType::Publisher, pub_id)).unwrap();
             }
         }
         println!("ad revenue:{:?}", ad_revenue);
-        assert!(ad_revenue > 900.);
+        assert!(ad_revenue > 18000.);
 
-        let mut platform = Platform::new(0);
+        let mut platform = Platform::new(0, Ranker::Trust);
         platform.data = 100000.;
         platform.update_conversion_rate(conf.max_conversion_rate);
         let platforms = vec![platform];
@@ -1714,21 +1637,15 @@ mod tests {
         let mut new_ad_revenue = 0.;
         let author_id = consumers.len();
         for _ in 0..10 {
-            let content: Vec<Content> = (0..100).map(|i| {
-                Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(pub_id),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                }
+            let content: Vec<Content> = (0..100).map(|_| {
+                new_content(author_id, Some(pub_id), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                })
             }).collect();
 
             let mut c: FnvHashMap<(SharerType, usize), Vec<Content>> = FnvHashMap::default();
@@ -1737,22 +1654,19 @@ mod tests {
             ad_market(&mut c, &consumers, &publishers, &platforms, &conf, &mut rng);
 
             let content = c.remove(&(SharerType::Publisher, pub_id)).unwrap();
-            let shared_content: Vec<SharedContent> = content.into_iter().map(|c| SharedContent {
-                content: Arc::new(c),
-                sharer: (SharerType::Agent, author_id)
-            }).collect();
+            let shared_content: Vec<SharedContent> = content.into_iter().map(|c| new_shared(c, (SharerType::Agent, author_id))).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = shared_content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, _, _, _, revenue) = a.consume(&shared, &conf, &mut rng);
+                let revenue = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).revenue;
                 new_ad_revenue += revenue.get(&(SharerType::Publisher, pub_id)).unwrap();
             }
         }
         println!("new ad revenue:{:?}", new_ad_revenue);
         assert!(ad_revenue > new_ad_revenue);
-        assert!(new_ad_revenue < 200.);
+        assert!(new_ad_revenue < 4000.);
     }
 
     #[test]
@@ -1762,7 +1676,7 @@ mod tests {
             attention_budget: 20.
         };
 
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut consumers = standard_agents(&conf, &mut rng);
         for a in &mut consumers {
             a.relevancies.push(1.0);
@@ -1774,32 +1688,23 @@ mod tests {
         // Assume all consumers follow sharer initially
         let mut followers = vec![0, consumers.len() as isize];
         for _ in 0..10 {
-            let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(0),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![1., 1.]),
-                        values: Values::from_vec(vec![0., 0.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, sharer_id)
-                }
+            let content: Vec<SharedContent> = (0..100).map(|_| {
+                let content = new_content(author_id, Some(0), ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, sharer_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, _, (follows, unfollows), _, _) = a.consume(&shared, &conf, &mut rng);
+                let (follows, unfollows) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).follows;
                 for id in follows {
                     if id == author_id {
                         followers[0] += 1;
@@ -1822,32 +1727,23 @@ mod tests {
         assert_eq!(followers[1], consumers.len() as isize); // Shouldn't have changed
 
         for _ in 0..10 {
-            let content: Vec<SharedContent> = (0..100).map(|i| {
-                let content = Content {
-                    id: ContentId::new_v4(),
-                    publisher: Some(0),
-                    author: author_id,
-                    body: ContentBody {
-                        topics: Topics::from_vec(vec![ 0., 0.]),
-                        values: Values::from_vec(vec![-1., -1.]),
-                        cost: 1.,
-                        // quality: 1.,
-                        depth: 1.,
-                        spectacle: 1.,
-                    },
-                    ads: 0.
-                };
-                SharedContent {
-                    content: Arc::new(content),
-                    sharer: (SharerType::Agent, sharer_id)
-                }
+            let content: Vec<SharedContent> = (0..100).map(|_| {
+                let content = new_content(author_id, Some(0), ContentBody {
+                    topics: Topics::from_vec(vec![ 0., 0.]),
+                    values: Values::from_vec(vec![-1., -1.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                new_shared(content, (SharerType::Agent, sharer_id))
             }).collect();
 
             let mut shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
                 .map(|c| (None, c)).collect();
             for a in &mut consumers {
                 shared.shuffle(&mut rng);
-                let (_, _, (follows, unfollows), _, _) = a.consume(&shared, &conf, &mut rng);
+                let (follows, unfollows) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).follows;
                 for id in follows {
                     if id == author_id {
                         followers[0] += 1;
@@ -1869,4 +1765,196 @@ mod tests {
         assert!(followers[0] <= 0);
         assert!(followers[1] <= 0);
     }
+
+    #[test]
+    fn follow_intermediate_sharers() {
+        let mut conf = SimulationConfig::default();
+        conf.agent = AgentConfig {
+            attention_budget: 20.
+        };
+
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut consumers = standard_agents(&conf, &mut rng);
+        for a in &mut consumers {
+            a.relevancies.push(1.0);
+        }
+
+        let author_id = consumers.len();
+        let resharer_id = author_id + 1;
+        let sharer_id = author_id + 2;
+
+        let mut follows_resharer = 0;
+        for _ in 0..10 {
+            let content: Vec<SharedContent> = (0..100).map(|_| {
+                let content = new_content(author_id, None, ContentBody {
+                    topics: Topics::from_vec(vec![1., 1.]),
+                    values: Values::from_vec(vec![0., 0.]),
+                    cost: 1.,
+                    depth: 1.,
+                    spectacle: 1.,
+                    veracity: 1.,
+                });
+                let original = new_shared(content, (SharerType::Agent, author_id));
+                original.reshare(resharer_id, 0).reshare(sharer_id, 0)
+            }).collect();
+            assert!(content.iter().all(|sc| sc.depth() == 2));
+
+            let shared: Vec<(Option<&PlatformId>, &SharedContent)> = content.iter()
+                .map(|c| (None, c)).collect();
+            for a in &mut consumers {
+                let (follows, _) = a.consume(&shared, &FnvHashSet::default(), &conf, &mut rng).follows;
+                if follows.contains(&resharer_id) {
+                    follows_resharer += 1;
+                }
+            }
+        }
+
+        // Agents discover who passed the content on
+        assert!(follows_resharer >= consumers.len());
+    }
+
+    #[test]
+    fn content_stats() {
+        let conf = SimulationConfig::default();
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut consumers = standard_agents(&conf, &mut rng);
+        for a in &mut consumers {
+            a.relevancies.push(1.0);
+        }
+
+        let content = Arc::new(Content { ads: 1., ..new_content(consumers.len(), Some(0), ContentBody {
+            topics: Topics::from_vec(vec![1., 1.]),
+            values: Values::from_vec(vec![0., 0.]),
+            cost: 1.,
+            depth: 1.,
+            spectacle: 1.,
+            veracity: 1.,
+        }) });
+        let shared = new_shared(content.clone(), (SharerType::Publisher, 0));
+
+        // Extra references aren't shares
+        let _archive = [content.clone(), content.clone()];
+
+        let mut n_shared = 0;
+        for a in &mut consumers {
+            // Seen content is skipped the second time
+            let to_read = vec![(None, &shared), (None, &shared)];
+            let will_share = a.consume(&to_read, &FnvHashSet::default(), &conf, &mut rng).to_share;
+            n_shared += will_share.len();
+        }
+        assert_eq!(content.stats.views(), consumers.len());
+        assert_eq!(content.stats.ad_impressions(), consumers.len());
+        assert_eq!(content.stats.shares(), n_shared);
+    }
+
+    #[test]
+    fn paywall_preview() {
+        let conf = SimulationConfig::default();
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut consumers = standard_agents(&conf, &mut rng);
+        for a in &mut consumers {
+            a.relevancies.push(1.0);
+            a.media_literacy = 1.;
+        }
+
+        let content = Arc::new(Content { ads: 1., paywalled: true, ..new_content(consumers.len(), Some(0), ContentBody {
+            topics: Topics::from_vec(vec![1., 1.]),
+            values: Values::from_vec(vec![0., 0.]),
+            cost: 1.,
+            depth: 1.,
+            spectacle: 1.,
+            veracity: 1.,
+        }) });
+        let shared = new_shared(content.clone(), (SharerType::Publisher, 0));
+
+        // Half of the Agents are subscribers
+        let n_subscribers = consumers.len()/2;
+        for a in &mut consumers[..n_subscribers] {
+            a.subscriptions.insert(0);
+        }
+
+        let mut n_shared = 0;
+        let mut n_paid = 0;
+        for (i, a) in consumers.iter_mut().enumerate() {
+            let to_read = vec![(None, &shared)];
+            let consumed = a.consume(&to_read, &FnvHashSet::default(), &conf, &mut rng);
+            let will_share = consumed.to_share;
+            let revenue = consumed.revenue;
+            if i >= n_subscribers {
+                assert!(will_share.is_empty());
+                assert!(revenue.is_empty());
+            } else {
+                n_paid += revenue.len();
+            }
+            n_shared += will_share.len();
+
+            // Everyone forms an opinion of the Publisher
+            assert!(a.publishers.contains_key(&0));
+        }
+        assert!(n_shared > 0);
+        assert_eq!(n_paid, n_subscribers);
+        assert_eq!(content.stats.views(), consumers.len());
+        assert_eq!(content.stats.ad_impressions(), n_subscribers);
+    }
+
+    #[test]
+    fn false_content() {
+        let mut conf = SimulationConfig::default();
+        conf.false_penalty = 0.2;
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut literate = Agent::new(0, &conf, &mut rng);
+        let mut gullible = Agent::new(1, &conf, &mut rng);
+        literate.media_literacy = 1.;
+        gullible.media_literacy = 0.;
+
+        let content = Arc::new(new_content(2, None, ContentBody {
+            topics: Topics::from_vec(vec![1., 1.]),
+            values: Values::from_vec(vec![0., 0.]),
+            cost: 1.,
+            depth: 1.,
+            spectacle: 1.,
+            veracity: 0.,
+        }));
+        assert!(content.body.is_false());
+        let shared = SharedContent { chain: vec![(SharerType::Agent, 2)], ..new_shared(content.clone(), (SharerType::Agent, 3)) };
+
+        let to_read = vec![(None, &shared)];
+        let will_share = literate.consume(&to_read, &FnvHashSet::default(), &conf, &mut rng).to_share;
+        assert!(will_share.is_empty());
+        assert_eq!(content.stats.flags(), 1);
+        assert_eq!(literate.trust[&2], conf.default_trust - conf.false_penalty);
+        assert_eq!(literate.trust[&3], conf.default_trust - conf.false_penalty);
+
+        gullible.consume(&to_read, &FnvHashSet::default(), &conf, &mut rng);
+        assert_eq!(content.stats.flags(), 1);
+        assert!(gullible.trust[&3] != conf.default_trust - conf.false_penalty);
+
+        // Once it's labeled, even the gullible
+        // take a FactChecker's word for it
+        conf.fact_checker.label_trust = 1.;
+        content.label.set(Verdict::False);
+        let mut gullible = Agent::new(1, &conf, &mut rng);
+        gullible.media_literacy = 0.;
+        let will_share = gullible.consume(&to_read, &FnvHashSet::default(), &conf, &mut rng).to_share;
+        assert!(will_share.is_empty());
+        assert_eq!(content.stats.flags(), 2);
+        assert_eq!(gullible.trust[&3], conf.default_trust - conf.false_penalty);
+    }
+
+    #[test]
+    fn willing_to_pay() {
+        let conf = SimulationConfig::default();
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &conf, &mut rng);
+        agent.resources = 100.;
+        agent.publishers.insert(0, (1., 0));
+        agent.publishers.insert(1, (0.1, 0));
+
+        assert!(agent.willing_to_pay(&0, 0., &conf));
+        assert!(agent.willing_to_pay(&0, 5., &conf));
+        assert!(!agent.willing_to_pay(&1, 5., &conf));
+
+        agent.resources = 10.;
+        assert!(!agent.willing_to_pay(&0, 5., &conf));
+    }
 }
@@ -0,0 +1,228 @@
+use super::agent::AgentId;
+use super::content::{Content, ContentId, SharedContent};
+use super::config::PlatformConfig;
+use super::platform::Platform;
+use super::policy::PolicyId;
+use rand_chacha::ChaCha20Rng;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::{Serialize, Deserialize};
+
+// What a moderation Rule looks for
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Flag {
+    // Any value further from the center than the threshold
+    ExtremeValues,
+
+    // Spectacle exceeding depth by more than the threshold
+    Sensationalism,
+
+    // Authors the Platform estimates its users
+    // trust less than the threshold, on average
+    LowTrust,
+
+    // Authors with more than the threshold pieces
+    // in their share queue, which is what bots look like
+    Bots,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct Rule {
+    pub flag: Flag,
+    pub threshold: f32,
+
+    // Chance flagged content is still shown
+    // in a feed, i.e. how much it's down-ranked.
+    // 0 removes it outright.
+    #[serde(default)]
+    pub visibility: f32,
+
+    // The Policy that added it, so the
+    // same Rule is removed when it's reverted.
+    // None for Rules from the config.
+    #[serde(default)]
+    pub policy: Option<PolicyId>,
+}
+
+impl Rule {
+    pub fn flags(&self, content: &Content, reputations: &FnvHashMap<AgentId, f32>, activity: &FnvHashMap<AgentId, usize>) -> bool {
+        match self.flag {
+            Flag::ExtremeValues => content.body.values.iter().any(|v| v.abs() > self.threshold),
+            Flag::Sensationalism => content.body.spectacle - content.body.depth > self.threshold,
+            Flag::LowTrust => match reputations.get(&content.author) {
+                Some(r) => *r < self.threshold,
+                None => false
+            },
+            Flag::Bots => match activity.get(&content.author) {
+                Some(n) => *n as f32 > self.threshold,
+                None => false
+            }
+        }
+    }
+}
+
+// What a Platform's moderation did in the last step
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ModerationStats {
+    pub removed: usize,
+    pub downranked: usize,
+
+    // Content that was wrongly acted on
+    // or wrongly let through
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+// Decide how visible the content the Platform's users
+// shared is. Only content that's acted on is included.
+// Each piece is moderated once per Platform per step,
+// however many of its users shared it.
+// Rules are applied imperfectly: flagged content is missed
+// at the false negative rate and unflagged content is acted on
+// at the false positive rate, as if by a randomly chosen rule.
+pub fn moderate(platform: &mut Platform, share_queues: &[Vec<SharedContent>], conf: &PlatformConfig, rng: &mut ChaCha20Rng) -> FnvHashMap<ContentId, f32> {
+    let mut visibility = FnvHashMap::default();
+    let mut stats = ModerationStats::default();
+    if platform.moderation.is_empty() {
+        platform.moderated = stats;
+        return visibility;
+    }
+
+    let reputations = if platform.moderation.iter().any(|r| r.flag == Flag::LowTrust) {
+        platform.reputations()
+    } else {
+        FnvHashMap::default()
+    };
+    let activity: FnvHashMap<AgentId, usize> = if platform.moderation.iter().any(|r| r.flag == Flag::Bots) {
+        platform.users().into_iter().map(|a_id| (a_id, share_queues[a_id].len())).collect()
+    } else {
+        FnvHashMap::default()
+    };
+    let mut seen: FnvHashSet<ContentId> = FnvHashSet::default();
+    for a_id in platform.users() {
+        for sc in &share_queues[a_id] {
+            let content = &sc.content;
+            if !seen.insert(content.id) {
+                continue;
+            }
+            let flagged = platform.moderation.iter()
+                .filter(|r| r.flags(content, &reputations, &activity))
+                .map(|r| r.visibility)
+                .fold(None, |acc: Option<f32>, v| Some(acc.map_or(v, |a| a.min(v))));
+            let action = match flagged {
+                Some(v) => {
+                    if conf.false_negative_rate > 0. && rng.gen::<f32>() < conf.false_negative_rate {
+                        stats.false_negatives += 1;
+                        None
+                    } else {
+                        Some(v)
+                    }
+                },
+                None => {
+                    if conf.false_positive_rate > 0. && rng.gen::<f32>() < conf.false_positive_rate {
+                        stats.false_positives += 1;
+                        platform.moderation.choose(rng).map(|r| r.visibility)
+                    } else {
+                        None
+                    }
+                }
+            };
+            if let Some(v) = action {
+                if v > 0. {
+                    stats.downranked += 1;
+                } else {
+                    stats.removed += 1;
+                }
+                visibility.insert(content.id, v);
+            }
+        }
+    }
+    platform.moderated = stats;
+    visibility
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::feed::Ranker;
+    use super::super::agent::Values;
+    use super::super::content::{ContentBody, SharerType};
+    use super::super::testing::{new_body, new_content, new_shared};
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    fn content(author: AgentId, values: Vec<f32>, spectacle: f32) -> Arc<Content> {
+        Arc::new(new_content(author, None, ContentBody {
+            values: Values::from_vec(values),
+            depth: 0.5,
+            spectacle,
+            ..new_body()
+        }))
+    }
+
+    fn shared(content: &Arc<Content>) -> SharedContent {
+        new_shared(content.clone(), (SharerType::Agent, content.author))
+    }
+
+    #[test]
+    fn moderation_rules() {
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut conf = PlatformConfig::default();
+        let mut platform = Platform::new(0, Ranker::Trust);
+        for a_id in 0..3 {
+            platform.signup(a_id);
+        }
+        let extreme = content(0, vec![0.9, 0.], 0.);
+        let sensational = content(1, vec![0., 0.], 1.);
+        let share_queues = vec![
+            vec![shared(&extreme)],
+            vec![shared(&sensational), shared(&extreme)],
+            vec![shared(&content(2, vec![0.1, 0.1], 0.))],
+        ];
+
+        // Nothing is moderated without rules
+        assert!(moderate(&mut platform, &share_queues, &conf, &mut rng).is_empty());
+
+        platform.moderation.push(Rule { flag: Flag::ExtremeValues, threshold: 0.8, visibility: 0., policy: None });
+        platform.moderation.push(Rule { flag: Flag::Sensationalism, threshold: 0.2, visibility: 0.5, policy: None });
+        let visibility = moderate(&mut platform, &share_queues, &conf, &mut rng);
+        assert_eq!(visibility.len(), 2);
+        assert_eq!(visibility[&extreme.id], 0.);
+        assert_eq!(visibility[&sensational.id], 0.5);
+        assert_eq!(platform.moderated.removed, 1);
+        assert_eq!(platform.moderated.downranked, 1);
+
+        // Every flag missed, everything else
+        // acted on as if by one of the rules
+        conf.false_negative_rate = 1.;
+        conf.false_positive_rate = 1.;
+        let visibility = moderate(&mut platform, &share_queues, &conf, &mut rng);
+        assert_eq!(visibility.len(), 1);
+        assert!(visibility.values().all(|v| *v == 0. || *v == 0.5));
+        assert_eq!(platform.moderated.false_negatives, 2);
+        assert_eq!(platform.moderated.false_positives, 1);
+        assert_eq!(platform.moderated.removed + platform.moderated.downranked, 1);
+
+        // Authors the Platform thinks are untrusted
+        conf.false_negative_rate = 0.;
+        conf.false_positive_rate = 0.;
+        platform.moderation = vec![Rule { flag: Flag::LowTrust, threshold: 0.5, visibility: 0., policy: None }];
+        platform.follow(&1, &0);
+        platform.follow(&0, &1);
+        for _ in 0..10 {
+            platform.observe(1, 0, false, 0.5, 0., &mut rng);
+            platform.observe(0, 1, true, 0.5, 0., &mut rng);
+        }
+        let visibility = moderate(&mut platform, &share_queues, &conf, &mut rng);
+        assert_eq!(visibility.len(), 1);
+        assert!(visibility.contains_key(&extreme.id));
+
+        // Authors posting in bulk
+        platform.moderation = vec![Rule { flag: Flag::Bots, threshold: 1., visibility: 0., policy: None }];
+        let visibility = moderate(&mut platform, &share_queues, &conf, &mut rng);
+        assert_eq!(visibility.len(), 1);
+        assert!(visibility.contains_key(&sensational.id));
+    }
+}
@@ -1,21 +1,24 @@
 use super::agent::{Agent, AgentId};
-use fnv::FnvHashMap;
-use rand::rngs::StdRng;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use rand_chacha::ChaCha20Rng;
 use rand::seq::SliceRandom;
 use rand::Rng;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Network {
-    incoming: FnvHashMap<AgentId, Vec<AgentId>>,
-    outgoing: FnvHashMap<AgentId, Vec<AgentId>>,
+    incoming: BTreeMap<AgentId, Vec<AgentId>>,
+    outgoing: BTreeMap<AgentId, Vec<AgentId>>,
     total_edges: f32,
 }
 
 impl Network {
     pub fn new() -> Network {
         Network {
-            incoming: FnvHashMap::default(),
-            outgoing: FnvHashMap::default(),
+            incoming: BTreeMap::new(),
+            outgoing: BTreeMap::new(),
             total_edges: 0.,
         }
     }
@@ -35,11 +38,11 @@ impl Network {
         self.total_edges += 1.;
     }
 
-    pub fn preferential_attachment(&mut self, agents: &Vec<Agent>, max_friends: usize, mut rng: &mut StdRng) {
+    pub fn preferential_attachment(&mut self, agents: &[Agent], max_friends: usize, mut rng: &mut ChaCha20Rng) {
         let mut new = Vec::new();
 
         // Network of agents, with trust as weight
-        for agent in agents {
+        for agent in agents.iter().filter(|a| a.alive) {
             if !self.incoming.contains_key(&agent.id) {
                 self.add_node(agent.id);
                 new.push(agent);
@@ -48,10 +51,14 @@ impl Network {
 
         // Social network formation (preferential attachment)
         for agent in new {
+            // Bots have no offline ties
+            if agent.bot {
+                continue;
+            }
             let idx = &agent.id;
             let sample_size = (rng.gen::<f32>() * max_friends as f32).floor() as usize;
             let candidates = agents.choose_multiple(&mut rng, sample_size);
-            for candidate in candidates {
+            for candidate in candidates.filter(|c| c.alive && !c.bot) {
                 // Probability that two Agents know each other
                 // based on:
                 // 1. their similarity
@@ -70,18 +77,42 @@ impl Network {
 
     }
 
+    // Remove a node along with all its edges
+    pub fn remove_node(&mut self, id: &AgentId) {
+        if let Some(outgoing) = self.outgoing.remove(id) {
+            for b in &outgoing {
+                self.incoming.get_mut(b).unwrap().retain(|x| x != id);
+            }
+            self.total_edges -= outgoing.len() as f32;
+        }
+        if let Some(incoming) = self.incoming.remove(id) {
+            for a in &incoming {
+                self.outgoing.get_mut(a).unwrap().retain(|x| x != id);
+            }
+            self.total_edges -= incoming.len() as f32;
+        }
+    }
+
     pub fn exists(&self, a: &AgentId) -> bool {
         // Added to both outgoing/incoming,
         // only need to check one
         self.incoming.contains_key(a)
     }
 
+    pub fn nodes(&self) -> impl Iterator<Item=AgentId> + '_ {
+        self.incoming.keys().cloned()
+    }
+
     pub fn n_nodes(&self) -> usize {
         // Added to both outgoing/incoming,
         // only need to check one
         self.incoming.len()
     }
 
+    pub fn n_edges(&self) -> usize {
+        self.total_edges as usize
+    }
+
     pub fn n_followers(&self) -> Vec<usize> {
         self.incoming.values().map(|v| v.len()).collect()
     }
@@ -92,9 +123,119 @@ impl Network {
 
     pub fn remove_edges(&mut self, a: &AgentId, b: &AgentId) {
         let outgoing = self.outgoing.get_mut(a).unwrap();
+        let n = outgoing.len();
         outgoing.retain(|x| x != b);
+        self.total_edges -= (n - outgoing.len()) as f32;
 
         let incoming = self.incoming.get_mut(b).unwrap();
         incoming.retain(|x| x != a);
     }
+
+    // Mean local clustering coefficient of the given nodes,
+    // ignoring which way ties go
+    pub fn clustering(&self, nodes: &[AgentId]) -> f32 {
+        let nodes: Vec<&AgentId> = nodes.iter().filter(|a| self.exists(a)).collect();
+        if nodes.is_empty() {
+            return 0.;
+        }
+
+        let total: f32 = nodes.iter().map(|a| {
+            let ns: FnvHashSet<AgentId> = self.outgoing[a].iter()
+                .chain(self.incoming[a].iter())
+                .filter(|b| *b != *a)
+                .cloned()
+                .collect();
+            let k = ns.len();
+            if k < 2 {
+                return 0.;
+            }
+
+            // Ties either way between neighbors count once
+            let mut links: FnvHashSet<(AgentId, AgentId)> = FnvHashSet::default();
+            for b in &ns {
+                for c in self.outgoing[b].iter().filter(|c| *c != b && ns.contains(c)) {
+                    links.insert((*b.min(c), *b.max(c)));
+                }
+            }
+            links.len() as f32 / (k * (k - 1) / 2) as f32
+        }).sum();
+        total / nodes.len() as f32
+    }
+
+    // Modularity of the network when nodes
+    // are split into the given communities
+    pub fn modularity<K: Hash + Eq, F: Fn(&AgentId) -> K>(&self, community: F) -> f32 {
+        let communities: FnvHashMap<AgentId, K> = self.nodes()
+            .map(|a| {
+                let c = community(&a);
+                (a, c)
+            })
+            .collect();
+        let m = self.outgoing.values().map(|bs| bs.len()).sum::<usize>() as f32;
+        if m == 0. {
+            return 0.;
+        }
+
+        // Ties within communities and the total
+        // out/in degree of each community
+        let mut within = 0.;
+        let mut degrees: FnvHashMap<&K, (f32, f32)> = FnvHashMap::default();
+        for (a, bs) in &self.outgoing {
+            let c = &communities[a];
+            within += bs.iter().filter(|b| communities[b] == *c).count() as f32;
+            let degree = degrees.entry(c).or_insert((0., 0.));
+            degree.0 += bs.len() as f32;
+            degree.1 += self.incoming[a].len() as f32;
+        }
+        within / m - degrees.values().map(|(out_, in_)| (out_ / m) * (in_ / m)).sum::<f32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two triangles joined by a single tie
+    fn network() -> Network {
+        let mut network = Network::new();
+        for a in 0..6 {
+            network.add_node(a);
+        }
+        for (a, b) in &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)] {
+            network.add_edge(a, b);
+        }
+        network
+    }
+
+    #[test]
+    fn clustering() {
+        let network = network();
+        let nodes: Vec<AgentId> = (0..6).collect();
+        // Nodes 2 and 3 have 3 neighbors with 1 link among them
+        let expected = (4. + 2./3.) / 6.;
+        assert!((network.clustering(&nodes) - expected).abs() < 1e-6);
+        assert_eq!(network.clustering(&[0, 1]), 1.);
+        assert_eq!(Network::new().clustering(&nodes), 0.);
+    }
+
+    #[test]
+    fn modularity() {
+        let network = network();
+        let split = network.modularity(|a| *a < 3);
+        let mixed = network.modularity(|a| a % 2 == 0);
+        assert!(split > 0.3);
+        assert!(mixed < split);
+        assert_eq!(network.modularity(|_| 0), 0.);
+    }
+
+    #[test]
+    fn remove_edges() {
+        let mut network = network();
+        network.remove_edges(&2, &3);
+        network.remove_edges(&3, &2);
+        assert_eq!(network.n_edges(), 6);
+        network.remove_node(&0);
+        assert_eq!(network.n_edges(), 4);
+        assert!(network.following_ids(&2).is_empty());
+    }
 }
@@ -1,28 +1,76 @@
-use super::agent::AgentId;
+use super::agent::{Agent, AgentId};
 use super::network::Network;
+use super::content::SharedContent;
+use super::config::SimulationConfig;
+use super::feed::Ranker;
+use super::moderation::{Rule, ModerationStats};
 use super::util;
+use rand_chacha::ChaCha20Rng;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use itertools::Itertools;
+use fnv::FnvHashMap;
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
 
 pub type PlatformId = usize;
 
 // A Platform provides a way for
 // content to circulate more rapidly.
 // It doesn't publish any content itself
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Platform {
     pub id: PlatformId,
     pub data: f32,
     pub conversion_rate: f32,
+    pub ranker: Ranker,
     network: Network,
+
+    // False once the Platform has shut down
+    pub active: bool,
+
+    // Ad revenue funds operating costs.
+    // `ad_revenue` is the part of the last step's
+    // revenue that came from the Platform's cut of
+    // content creators' ad revenue.
+    pub budget: f32,
+    pub revenue: f32,
+    pub ad_revenue: f32,
+
+    // Consecutive steps the Platform
+    // hasn't been able to cover its costs
+    pub insolvent_steps: usize,
+
+    // Rules content is moderated by,
+    // and what moderation did in the last step
+    pub moderation: Vec<Rule>,
+    pub moderated: ModerationStats,
+
+    // The Platform's estimate of how much
+    // an Agent trusts another, keyed by (truster, trusted).
+    // Learned from what the Platform can observe,
+    // i.e. whether Agents reshare what they see.
+    // Only kept for Agents that follow each other.
+    trust: BTreeMap<(AgentId, AgentId), f32>,
 }
 
 impl Platform {
-    pub fn new(id: PlatformId) -> Platform {
+    pub fn new(id: PlatformId, ranker: Ranker) -> Platform {
         let network = Network::new();
         Platform {
             id: id,
             network: network,
             data: 0.,
             conversion_rate: 0.,
+            ranker,
+            trust: BTreeMap::new(),
+            active: true,
+            budget: 0.,
+            revenue: 0.,
+            ad_revenue: 0.,
+            insolvent_steps: 0,
+            moderation: Vec::new(),
+            moderated: ModerationStats::default(),
         }
     }
 
@@ -30,12 +78,30 @@ impl Platform {
         self.network.add_node(a);
     }
 
+    // Remove the Agent and everything
+    // the Platform knows about them
+    pub fn remove_user(&mut self, a: &AgentId) {
+        self.network.remove_node(a);
+        self.trust.retain(|(x, y), _| x != a && y != a);
+    }
+
+    // Remove all users, returning who they were
+    pub fn shut_down(&mut self) -> Vec<AgentId> {
+        let users = self.users();
+        self.active = false;
+        self.conversion_rate = 0.;
+        self.network = Network::new();
+        self.trust.clear();
+        users
+    }
+
     pub fn is_signed_up(&self, a: &AgentId) -> bool {
         self.network.exists(a)
     }
 
     pub fn unfollow(&mut self, a: &AgentId, b: &AgentId) {
         self.network.remove_edges(a, b);
+        self.trust.remove(&(*a, *b));
     }
 
     pub fn follow(&mut self, a: &AgentId, b: &AgentId) {
@@ -46,6 +112,50 @@ impl Platform {
         self.network.following_ids(a)
     }
 
+    // Signed up Agents, in id order
+    pub fn users(&self) -> Vec<AgentId> {
+        self.network.nodes().sorted().collect()
+    }
+
+    // Content the Agent sees on this Platform
+    pub fn feed<'a>(&self, agent: &Agent, share_queues: &'a [Vec<SharedContent>], conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<&'a SharedContent> {
+        self.ranker.ranker().feed(agent, self, share_queues, conf, rng)
+    }
+
+    // Estimated trust of Agent `a` for Agent `b`
+    pub fn trust(&self, a: &AgentId, b: &AgentId) -> Option<f32> {
+        self.trust.get(&(*a, *b)).cloned()
+    }
+
+    // How much each Agent is trusted by others,
+    // on average, as estimated by the Platform
+    pub fn reputations(&self) -> FnvHashMap<AgentId, f32> {
+        let mut totals: FnvHashMap<AgentId, (f32, usize)> = FnvHashMap::default();
+        for ((_, b), v) in &self.trust {
+            let total = totals.entry(*b).or_insert((0., 0));
+            total.0 += v;
+            total.1 += 1;
+        }
+        totals.into_iter().map(|(a_id, (sum, n))| (a_id, sum/n as f32)).collect()
+    }
+
+    // Agent `a` saw content shared by Agent `b`
+    // on this Platform and did or didn't reshare it.
+    // The Platform only gets a noisy read of that,
+    // and starts from `prior` for Agents it hasn't seen.
+    pub fn observe(&mut self, a: AgentId, b: AgentId, engaged: bool, prior: f32, noise: f32, rng: &mut ChaCha20Rng) {
+        let mut signal = if engaged { 1. } else { 0. };
+        if noise > 0. {
+            let err: f32 = rng.sample(StandardNormal);
+            signal += err * noise;
+        }
+        if !self.network.exists(&a) || !self.network.following_ids(&a).contains(&b) {
+            return;
+        }
+        let estimate = self.trust.entry((a, b)).or_insert(prior);
+        *estimate = util::clamp(util::ewma(signal, *estimate), 0., 1.);
+    }
+
     pub fn n_users(&self) -> usize {
         self.network.n_nodes()
     }
@@ -57,4 +167,14 @@ impl Platform {
     pub fn update_conversion_rate(&mut self, max_conversion_rate: f32) {
         self.conversion_rate = util::sigmoid(self.data-0.5) * max_conversion_rate;
     }
+
+    // The Platform's own ads, shown alongside `impressions`
+    // pieces of content, convert at its conversion rate.
+    // Along with its cut of content ad revenue
+    // this goes towards the Platform's operating cost.
+    pub fn earn(&mut self, impressions: f32, ad_cut_revenue: f32, revenue_per_ad: f32, operating_cost: f32) {
+        self.ad_revenue = ad_cut_revenue;
+        self.revenue = impressions * self.conversion_rate * revenue_per_ad + ad_cut_revenue;
+        self.budget += self.revenue - operating_cost;
+    }
 }
@@ -1,7 +1,11 @@
+use super::agent::AgentId;
+use super::platform::PlatformId;
+use super::moderation::Rule;
+use super::fact_checker::FactCheckerId;
 use serde::{Serialize, Deserialize};
 use strum_macros::{Display, EnumIter};
 
-#[derive(Display, EnumIter, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Display, EnumIter, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum Policy {
     PopulationChange(isize),
     SubsidizeProduction(f32),
@@ -9,4 +13,79 @@ pub enum Policy {
     FoundPlatforms(usize),
     MediaLiteracy(f32),
     Recession(f32),
+
+    // Threshold and visibility of
+    // a moderation Rule for all Platforms
+    ModerateExtremeValues(f32, f32),
+    ModerateSensationalism(f32, f32),
+    ModerateLowTrust(f32, f32),
+    ModerateBots(f32, f32),
+
+    FoundFactCheckers(usize),
+}
+
+impl Policy {
+    // Removed Agents are gone for good,
+    // so a shrinking population can't be taken back
+    pub fn is_reversible(&self) -> bool {
+        match self {
+            Policy::PopulationChange(n) => *n >= 0,
+            _ => true
+        }
+    }
+}
+
+pub type PolicyId = usize;
+
+// What's needed to take a Policy back
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum Undo {
+    // How much each config value was actually changed by
+    Recession {
+        economy: f32,
+        unsubscribe_lag: usize,
+        unsubscribe_trust: f32,
+        subscribe_trust: f32,
+        base_conversion_rate: f32,
+        revenue_per_ad: f32,
+    },
+
+    // How much each Agent's media literacy was raised
+    MediaLiteracy(Vec<(AgentId, f32)>),
+
+    // Platforms that were founded
+    FoundPlatforms(Vec<PlatformId>),
+
+    // Values before the Policy was applied
+    TaxAdvertising(f32),
+    SubsidizeProduction(f32),
+
+    // Agents that were added
+    AddedAgents(Vec<AgentId>),
+
+    // How many Agents were removed.
+    // This is only a record, it can't be reverted
+    RemovedAgents(usize),
+
+    // Moderation Rule that was added
+    Moderation(Rule),
+
+    // FactCheckers that were founded
+    FoundFactCheckers(Vec<FactCheckerId>),
+}
+
+// A Policy as applied to a Simulation
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRecord {
+    pub id: PolicyId,
+    pub policy: Policy,
+
+    // Step it was applied before
+    pub applied: usize,
+
+    // Step it's reverted before,
+    // None if it doesn't expire
+    pub until: Option<usize>,
+
+    pub undo: Undo,
 }
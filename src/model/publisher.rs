@@ -1,17 +1,30 @@
 use rand::Rng;
 use std::sync::Arc;
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use itertools::Itertools;
 use super::motive::Motive;
 use super::agent::{Agent, similarity, alignment};
-use super::content::{Content, ContentId, ContentBody};
-use super::util::{Vector, Learner, Sample, SampleRow, ewma, bayes_update, sigmoid, LimitedQueue, normal_range};
-use super::config::{PublisherConfig, SinglePublisherConfig};
+use super::content::{Content, ContentBody, ContentStats, Label, content_ref, new_content_id};
+use super::util::{Vector, Learner, SubLearner, Sample, SampleRow, ewma, bayes_update, sigmoid, LimitedQueue, normal_range};
+use super::config::SinglePublisherConfig;
 use super::config::SimulationConfig;
 use super::grid::Position;
+use serde::{Serialize, Deserialize};
 
 pub type PublisherId = usize;
 
+// How Publishers set their subscription price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Pricing {
+    Fixed(f32),
+
+    // Learned, up to this price
+    Learned(f32),
+}
+
+static STEPS_PRICE: usize = 10;
+static STEPS_FACT_CHECKING: usize = 10;
+
 // A Publisher is a platform which
 // exercises discretion of what
 // content circulates through it.
@@ -20,10 +33,17 @@ pub type PublisherId = usize;
 // (In contrast, on a Platform the content
 // that circulates through it carries
 // the reputation of the sender)
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Publisher {
     pub id: PublisherId,
 
+    // False once the Publisher has shut down
+    pub active: bool,
+
+    // Consecutive steps the budget
+    // has been below the bankruptcy threshold
+    pub insolvent_steps: usize,
+
     pub location: Position,
     pub radius: usize,
 
@@ -51,6 +71,21 @@ pub struct Publisher {
     // Params for estimating quality/ads mix
     learner: Learner,
 
+    // Subscription price per step, and
+    // what subscribers paid in the last step
+    pub price: f32,
+    price_learner: Option<SubLearner>,
+    pub subscription_revenue: f32,
+
+    // Whether non-subscribers only get
+    // a preview of the Publisher's content
+    pub paywall: bool,
+
+    // Chance of catching a false pitch,
+    // learned up to the configured maximum
+    pub fact_checking: f32,
+    fact_checking_learner: Option<SubLearner>,
+
     // A Publisher's "reach" is the mean shared
     // count of its content per step
     pub reach: f32,
@@ -68,6 +103,7 @@ pub struct Publisher {
 
     // Archive of content the Publisher
     // has published
+    #[serde(with = "content_ref::queue")]
     pub content: LimitedQueue<Arc<Content>>,
 
     // Publisher tries to guess
@@ -77,11 +113,26 @@ pub struct Publisher {
 }
 
 impl Publisher {
-    pub fn new(id: PublisherId, conf: &PublisherConfig, mut rng: &mut StdRng) -> Publisher {
+    pub fn new(id: PublisherId, conf: &SimulationConfig, mut rng: &mut ChaCha20Rng) -> Publisher {
         let learner = Learner::new(&mut rng);
         let params = learner.get_params();
+        let (price, price_learner) = match conf.publisher.pricing {
+            Pricing::Fixed(price) => (price, None),
+            Pricing::Learned(max_price) => {
+                let learner = SubLearner::new(STEPS_PRICE, 0., max_price, rng);
+                (learner.get_params(), Some(learner))
+            }
+        };
+        let (fact_checking, fact_checking_learner) = if conf.publisher.fact_checking > 0. {
+            let learner = SubLearner::new(STEPS_FACT_CHECKING, 0., conf.publisher.fact_checking, rng);
+            (learner.get_params(), Some(learner))
+        } else {
+            (0., None)
+        };
         Publisher {
             id: id,
+            active: true,
+            insolvent_steps: 0,
 
             radius: 0,
             location: (0, 0),
@@ -89,8 +140,8 @@ impl Publisher {
             motive: rng.gen(),
 
             expenses: 0.,
-            budget: conf.base_budget,
-            revenue_per_subscriber: conf.revenue_per_subscriber,
+            budget: conf.publisher.base_budget,
+            revenue_per_subscriber: conf.publisher.revenue_per_subscriber,
             reach: 0.,
 
             depth: params[0],
@@ -100,35 +151,58 @@ impl Publisher {
             learner: learner,
             n_ads_sold: 0.,
 
+            price,
+            price_learner,
+            subscription_revenue: 0.,
+            paywall: conf.publisher.paywall,
+            fact_checking,
+            fact_checking_learner,
+
             content: LimitedQueue::new(50),
             subscribers: 0,
             n_last_published: 0,
 
             // Priors
-            audience: Audience::new(&mut rng),
+            audience: Audience::new(conf, rng),
         }
     }
 
-    pub fn from_config(id: PublisherId, sconf: &SinglePublisherConfig, conf: &PublisherConfig, rng: &mut StdRng) -> Publisher {
+    pub fn from_config(id: PublisherId, sconf: &SinglePublisherConfig, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Publisher {
         let mut publisher = Publisher::new(id, conf, rng);
         publisher.budget = sconf.base_budget;
         publisher.motive = sconf.motive;
+        if let Some(price) = sconf.price {
+            publisher.price = price;
+            publisher.price_learner = None;
+        }
+        if let Some(paywall) = sconf.paywall {
+            publisher.paywall = paywall;
+        }
         publisher
     }
 
     // An Agent pitches a piece
     // of content to the publisher
-    pub fn pitch(&mut self, body: &ContentBody, author: &mut Agent, conf: &SimulationConfig, rng: &mut StdRng) -> (Option<Content>, bool) {
+    pub fn pitch(&mut self, body: &ContentBody, author: &mut Agent, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> (Option<Content>, bool) {
         // TODO publisher takes into account author location?
         let depth = self.depth + body.depth;
         let spectacle = self.spectacle + body.spectacle;
-        let cost = (depth + spectacle) * conf.cost_per_quality;
+        let cost = (depth + spectacle + self.fact_checking) * conf.cost_per_quality;
         if self.budget < cost { return (None, false); }
 
         // TODO this doesn't necessarily need to be random?
         // Could just be based on a threshold
         let p_accept = accept_prob(&body, &self.audience);
         let accepted = rng.gen::<f32>() < p_accept;
+
+        // Fact-checking is paid for whether
+        // or not it turns anything up
+        if accepted && body.is_false() && self.fact_checking > 0. && rng.gen::<f32>() < self.fact_checking {
+            let check_cost = self.fact_checking * conf.cost_per_quality;
+            self.budget -= check_cost;
+            self.expenses += check_cost;
+            return (None, true);
+        }
         if accepted {
             // Pay author
             author.resources += (body.depth + body.spectacle) * conf.cost_per_quality;
@@ -139,11 +213,14 @@ impl Publisher {
             body_.spectacle += self.spectacle;
             body_.cost = self.attention;
             let content = Content {
-                id: ContentId::new_v4(),
+                id: new_content_id(rng),
                 publisher: Some(self.id),
                 body: body_,
                 author: author.id,
-                ads: self.ads
+                ads: self.ads,
+                stats: ContentStats::default(),
+                paywalled: self.paywall,
+                label: Label::default(),
             };
 
             // Deduct from budget
@@ -155,8 +232,23 @@ impl Publisher {
         }
     }
 
+    // Whether subscribers pay a price
+    pub fn charges(&self) -> bool {
+        self.price_learner.is_some() || self.price > 0.
+    }
+
+    pub fn learns_price_or_checking(&self) -> bool {
+        self.price_learner.is_some() || self.fact_checking_learner.is_some()
+    }
+
+    // Subscribers who don't pay bring in
+    // revenue some other way
     pub fn regular_revenue(&self) -> f32 {
-        self.subscribers as f32 * self.revenue_per_subscriber
+        if self.charges() {
+            0.
+        } else {
+            self.subscribers as f32 * self.revenue_per_subscriber
+        }
     }
 
     // Update understanding of audience values/interests
@@ -169,16 +261,16 @@ impl Publisher {
     }
 
     pub fn content_by_popularity(&self) -> std::vec::IntoIter<&Arc<Content>> {
-        self.content.iter().sorted_by(|a, b| Arc::strong_count(b).cmp(&Arc::strong_count(a)))
+        self.content.iter().sorted_by(|a, b| b.stats.shares().cmp(&a.stats.shares()))
     }
 
     pub fn n_shares(&self) -> Vec<usize> {
-        self.content.iter().map(|c| Arc::strong_count(c)).collect()
+        self.content.iter().map(|c| c.stats.shares()).collect()
     }
 
     pub fn update_reach(&mut self) {
         let shares = self.n_shares();
-        if shares.len() == 0 {
+        if shares.is_empty() {
             self.reach = ewma(0., self.reach);
         } else {
             let mean_shares = shares.iter().fold(0, |acc, v| acc + v) as f32 / shares.len() as f32;
@@ -186,7 +278,7 @@ impl Publisher {
         }
     }
 
-    pub fn learn(&mut self, revenue: f32, update: bool, rng: &mut StdRng) {
+    pub fn learn(&mut self, revenue: f32, update: bool, rng: &mut ChaCha20Rng) {
         // Assume reach has been updated
         // Assume expenses are correct
         let reward = match self.motive {
@@ -195,6 +287,20 @@ impl Publisher {
             Motive::Influence => self.reach + f32::max(0., revenue - self.expenses),
         };
         self.learner.learn(reward);
+        if let Some(learner) = &mut self.price_learner {
+            learner.learn(reward);
+            if update {
+                learner.decide(rng);
+                self.price = learner.get_params();
+            }
+        }
+        if let Some(learner) = &mut self.fact_checking_learner {
+            learner.learn(reward);
+            if update {
+                learner.decide(rng);
+                self.fact_checking = learner.get_params();
+            }
+        }
         if update {
             self.learner.decide(rng);
             let params = self.learner.get_params();
@@ -207,30 +313,26 @@ impl Publisher {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Audience {
     pub values: (Vector, Vector),
     pub interests: (Vector, Vector),
 
+    // Scratch space for surveys
+    #[serde(skip)]
     val_sample: Vec<SampleRow>,
+    #[serde(skip)]
     int_sample: Vec<SampleRow>,
 }
 
 impl Audience {
-    pub fn new(mut rng: &mut StdRng) -> Audience {
-        let mu_values = Vector::from_vec(vec![
-            normal_range(&mut rng),
-            normal_range(&mut rng),
-        ]);
-        let mu_interests = Vector::from_vec(vec![
-            normal_range(&mut rng),
-            normal_range(&mut rng),
-        ]);
-        let var = Vector::from_vec(vec![0.5, 0.5]);
+    pub fn new(conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Audience {
+        let mu_values = Vector::from_fn(conf.value_dims, |_, _| normal_range(rng));
+        let mu_interests = Vector::from_fn(conf.topic_dims, |_, _| normal_range(rng));
 
         Audience {
-            values: (mu_values, var.clone()),
-            interests: (mu_interests, var.clone()),
+            values: (mu_values, Vector::from_element(conf.value_dims, 0.5)),
+            interests: (mu_interests, Vector::from_element(conf.topic_dims, 0.5)),
 
             val_sample: Vec::new(),
             int_sample: Vec::new(),
@@ -247,10 +349,10 @@ impl Audience {
         }
 
         let mut sample = Sample::from_rows(self.val_sample.as_slice());
-        self.values = bayes_update(self.values, sample);
+        self.values = bayes_update(&self.values, sample);
 
         sample = Sample::from_rows(self.int_sample.as_slice());
-        self.interests = bayes_update(self.interests, sample);
+        self.interests = bayes_update(&self.interests, sample);
     }
 }
 
@@ -269,6 +371,8 @@ pub fn accept_prob(body: &ContentBody, audience: &Audience) -> f32 {
 mod tests {
     use super::*;
     use super::super::agent::{Topics, Values};
+    use super::super::content::ContentId;
+    use rand::SeedableRng;
 
     #[test]
     fn test_audience_learning() {
@@ -291,11 +395,15 @@ mod tests {
                         cost: 0.,
                         depth: 0.,
                         spectacle: 0.,
+                        veracity: 1.,
                         topics: Topics::from_vec(vec![ 0., 1.]),
                         values: Values::from_vec(vec![-1., 1.]),
                     },
                     author: 0,
-                    ads: 0.
+                    ads: 0.,
+                    stats: ContentStats::default(),
+                    paywalled: false,
+                    label: Label::default(),
                 })
             }).collect();
             audience.update(sample);
@@ -310,11 +418,11 @@ mod tests {
             cost: 0.,
             depth: 0.,
             spectacle: 0.,
+            veracity: 1.,
             topics: Topics::from_vec(vec![ 0., 1.]),
             values: Values::from_vec(vec![-1., 1.]),
         };
 
-        let mu = Vector::from_vec(vec![0., 0.]);
         let var = Vector::from_vec(vec![0.5, 0.5]);
         let audience = Audience {
             values: (Values::from_vec(vec![-1., 1.]), var.clone()),
@@ -332,6 +440,7 @@ mod tests {
             cost: 0.,
             depth: 0.,
             spectacle: 0.,
+            veracity: 1.,
             topics: Topics::from_vec(vec![ 0., 0.]),
             values: Values::from_vec(vec![-1., 1.]),
         };
@@ -344,6 +453,7 @@ mod tests {
             cost: 0.,
             depth: 0.,
             spectacle: 0.,
+            veracity: 1.,
             topics: Topics::from_vec(vec![ 1., 0.]),
             values: Values::from_vec(vec![ 1.,-1.]),
         };
@@ -352,4 +462,49 @@ mod tests {
         assert_eq!(sim, 0.0);
         assert!(prob < 0.05);
     }
+
+    #[test]
+    fn fact_checking() {
+        let mut conf = SimulationConfig::default();
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
+        let mut author = Agent::new(0, &conf, &mut rng);
+        let mut body = ContentBody {
+            cost: 0.,
+            depth: 0.,
+            spectacle: 0.,
+            veracity: 0.,
+            topics: Topics::from_vec(vec![ 0., 1.]),
+            values: Values::from_vec(vec![-1., 1.]),
+        };
+
+        // Each Publisher learns how much to check
+        let publisher = Publisher::new(0, &conf, &mut rng);
+        assert_eq!(publisher.fact_checking, 0.);
+        conf.publisher.fact_checking = 1.;
+        let mut publisher = Publisher::new(0, &conf, &mut rng);
+        assert!(publisher.fact_checking >= 0. && publisher.fact_checking <= 1.);
+        let first = publisher.fact_checking;
+        let changed = (0..20).any(|_| {
+            publisher.learn(0., true, &mut rng);
+            publisher.fact_checking != first
+        });
+        assert!(changed);
+
+        publisher.fact_checking = 1.;
+        publisher.audience.values.0 = body.values.clone();
+        publisher.audience.interests.0 = body.topics.clone();
+        for _ in 0..10 {
+            let budget = publisher.budget;
+            let (content, pitched) = publisher.pitch(&body, &mut author, &conf, &mut rng);
+            assert!(pitched);
+            assert!(content.is_none());
+            assert!(publisher.budget <= budget);
+        }
+
+        body.veracity = 1.;
+        let published = (0..10)
+            .filter_map(|_| publisher.pitch(&body, &mut author, &conf, &mut rng).0)
+            .count();
+        assert!(published > 0);
+    }
 }
@@ -1,33 +1,75 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use fnv::{FnvHashMap, FnvHashSet};
-use super::agent::{Agent, AgentId};
-use super::policy::Policy;
+use std::collections::BTreeMap;
+use super::agent::{Agent, AgentId, Values};
+use super::policy::{Policy, PolicyId, PolicyRecord, Undo};
 use super::network::Network;
 use super::platform::{Platform, PlatformId};
-use super::publisher::Publisher;
+use super::publisher::{Publisher, PublisherId};
 use super::grid::{HexGrid, Position, hexagon_dist};
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use rand::seq::SliceRandom;
-use super::content::{Content, ContentId, SharedContent, SharerType};
-use super::util::{ewma, sigmoid};
+use super::content::{Content, ContentId, ContentStats, ContentExpiry, ExpiredContent, SharedContent, SharerType, Label, Verdict, content_ref, new_content_id};
+use super::cascade::{Cascade, Channel, ExpiredCascade};
+use super::util::{ewma, sigmoid, sorted_map, LimitedQueue, LimitedSet};
 use super::config::SimulationConfig;
+use super::feed::Ranker;
+use super::moderation::{self, Rule, Flag};
+use super::import::{self, Graph};
+use super::fact_checker::FactChecker;
 use itertools::Itertools;
 use rand_distr::{Distribution, Beta, Binomial};
 use std::sync::Arc;
-use std::cmp::max;
+use std::cmp::{max, min};
+use serde::{Serialize, Deserialize};
+use rayon::prelude::*;
 
 static MAX_FRIENDS: usize = 120;
 
+// What an Agent did during the consumption phase
+#[derive(Default)]
+struct Consumption {
+    to_share: Vec<(SharedContent, Channel)>,
+    subs: (Vec<PublisherId>, Vec<PublisherId>),
+    follows: (FnvHashSet<AgentId>, FnvHashSet<AgentId>),
+    data: FnvHashMap<PlatformId, f32>,
+    revenue: FnvHashMap<(SharerType, usize), f32>,
+    platform_revenue: FnvHashMap<PlatformId, f32>,
+    signup: Option<PlatformId>,
+    leaves: Vec<PlatformId>,
+
+    // What Platforms saw: who shared the content
+    // the Agent encountered there, and whether
+    // the Agent reshared it
+    observed: Vec<(PlatformId, AgentId, bool)>,
+}
+
+// Publishers entering or leaving the market,
+// and Platforms shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MarketEvent {
+    Entry(PublisherId),
+    Exit(PublisherId),
+    PlatformExit(PlatformId),
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Simulation {
     pub network: Network,
     pub agents: Vec<Agent>,
+    #[serde(with = "content_ref::vec")]
     content: Vec<Arc<Content>>,
     pub publishers: Vec<Publisher>,
     pub platforms: Vec<Platform>,
+    pub fact_checkers: Vec<FactChecker>,
     pub ref_grid: HexGrid,
-    pub grid: FnvHashMap<Position, Vec<AgentId>>,
+    pub grid: BTreeMap<Position, Vec<AgentId>>,
+    #[serde(with = "sorted_map")]
     pub distances: FnvHashMap<Position, Vec<usize>>,
 
+    // Steps run so far
+    step: usize,
+
     // Stats
     pub n_produced: usize,
     pub n_pitched: usize,
@@ -37,6 +79,10 @@ pub struct Simulation {
     advertising_tax: f32,
     subsidy: f32,
 
+    // Policies in effect, in the order they were applied
+    policies: Vec<PolicyRecord>,
+    n_policies: usize,
+
     // Content Agents will share in the next step.
     // Emptied each step.
     share_queues: Vec<Vec<SharedContent>>,
@@ -47,14 +93,64 @@ pub struct Simulation {
 
     // Agents and the platforms they're on
     agent_platforms: Vec<FnvHashSet<PlatformId>>,
+
+    // How content has spread through reshares
+    cascades: BTreeMap<ContentId, Cascade>,
+
+    // Content that's been dropped
+    expired: ExpiredContent,
+
+    // Cascades of Content dropped
+    // in the last step. Emptied each step.
+    expired_cascades: Vec<ExpiredCascade>,
+
+    // Publishers that entered or exited
+    // in the last step. Emptied each step.
+    market_events: Vec<MarketEvent>,
 }
 
 
 impl Simulation {
-    pub fn new(conf: &SimulationConfig, mut rng: &mut StdRng) -> Simulation {
-        let mut agents: Vec<Agent> = (0..conf.population)
-            .map(|i| Agent::new(i, &conf.agent, &mut rng))
+    pub fn new(conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Simulation {
+        Simulation::with_network(conf, import::load_network(conf), rng)
+    }
+
+    // The offline network may come from a file,
+    // which then also decides the population.
+    // For when it's already been read, see `config::set_network_population`.
+    pub fn with_network(conf: &SimulationConfig, graph: Option<Graph>, rng: &mut ChaCha20Rng) -> Simulation {
+        let population = graph.as_ref().map_or(conf.population, |g| g.n_nodes());
+
+        let mut agents: Vec<Agent> = (0..population + conf.n_bots)
+            .map(|i| Agent::new(i, conf, rng))
             .collect();
+        if let Some(graph) = &graph {
+            for (a, attrs) in agents.iter_mut().zip(graph.attributes.iter()) {
+                if let Some(values) = &attrs.values {
+                    assert_eq!(values.len(), conf.value_dims, "node values don't match VALUE_DIMS");
+                    a.values = values.clone();
+                }
+                if let Some(interests) = &attrs.interests {
+                    assert_eq!(interests.len(), conf.topic_dims, "node interests don't match TOPIC_DIMS");
+                    a.interests = interests.clone();
+                }
+            }
+        }
+
+        // Bots come after everyone else
+        // and all push the same values
+        if conf.n_bots > 0 {
+            let values = if conf.bot.values.is_empty() {
+                agents[population].values.clone()
+            } else {
+                assert_eq!(conf.bot.values.len(), conf.value_dims, "bot values don't match VALUE_DIMS");
+                Values::from_vec(conf.bot.values.clone())
+            };
+            for a in &mut agents[population..] {
+                a.bot = true;
+                a.values = values.clone();
+            }
+        }
 
         let mut agent_platforms = Vec::new();
         let mut share_queues = Vec::new();
@@ -65,11 +161,11 @@ impl Simulation {
 
         let mut publishers: Vec<Publisher> = conf.publishers.iter()
             .enumerate()
-            .map(|(i, sconf)| Publisher::from_config(i, &sconf, &conf.publisher, &mut rng))
+            .map(|(i, sconf)| Publisher::from_config(i, sconf, conf, rng))
             .collect();
 
         let more_publishers: Vec<Publisher> = (0..(conf.n_publishers - publishers.len()).max(0))
-            .map(|i| Publisher::new(i+publishers.len(), &conf.publisher, &mut rng))
+            .map(|i| Publisher::new(i+publishers.len(), conf, rng))
             .collect();
         publishers.extend(more_publishers);
 
@@ -78,58 +174,51 @@ impl Simulation {
             outboxes.push(Vec::new());
         }
 
-        let platforms: Vec<Platform> = (0..conf.n_platforms)
-            .map(|i| Platform::new(i))
+        let mut platforms: Vec<Platform> = conf.platforms.iter()
+            .enumerate()
+            .map(|(i, pconf)| new_platform(i, pconf.ranker, conf))
+            .collect();
+        let more_platforms: Vec<Platform> = (platforms.len()..conf.n_platforms)
+            .map(|i| new_platform(i, conf.platform.ranker, conf))
             .collect();
+        platforms.extend(more_platforms);
 
         let mut network = Network::new();
-        network.preferential_attachment(&agents, MAX_FRIENDS, &mut rng);
+        match &graph {
+            Some(graph) => {
+                for a in &agents {
+                    network.add_node(a.id);
+                }
+                for (a_id, b_id) in &graph.edges {
+                    network.add_edge(a_id, b_id);
+                }
+            },
+            None => network.preferential_attachment(&agents, MAX_FRIENDS, rng)
+        }
 
         let ref_grid = HexGrid::new(conf.grid_size, conf.grid_size);
-        let mut grid = FnvHashMap::default();
+        let mut grid = BTreeMap::new();
         for pos in ref_grid.positions() {
             grid.insert(pos, Vec::new());
         }
 
-        distribute_agents(&mut agents, &mut grid, rng);
+        // Agents the network file places stay put
+        let mut placed = FnvHashSet::default();
+        if let Some(graph) = &graph {
+            for (a_id, attrs) in graph.attributes.iter().enumerate() {
+                if let Some(pos) = attrs.location() {
+                    grid.get_mut(&pos).expect("node location is outside the grid").push(a_id);
+                    agents[a_id].location = pos;
+                    placed.insert(a_id);
+                }
+            }
+        }
+        distribute_agents(agents.iter_mut().filter(|a| !placed.contains(&a.id)), &mut grid, rng);
 
         // Randomly assign publishers by density
         let mut already_occupied: Vec<Position> = Vec::new();
-        let radii: Vec<usize> = (0..ref_grid.rows.max(ref_grid.cols)).collect();
-        let max_pop = grid.iter().fold(0, |acc, (_, agents)| agents.len().max(acc)) as f32;
         for publisher in &mut publishers {
-            // If all locations have a Publisher,
-            // reset to allow for multiple Publishers per location.
-            if already_occupied.len() == grid.keys().len() {
-                already_occupied.clear();
-            }
-            let weights: Vec<(Position, usize)> = grid.iter()
-                .filter(|(pos, agents)| !already_occupied.contains(pos) && agents.len() > 0)
-                .map(|(pos, agents)| (*pos, agents.len().pow(2)))
-                .collect();
-            // println!("weights {:?}", weights);
-            let pos = weights.choose_weighted(&mut rng, |item| item.1).unwrap().0;
-            publisher.location = pos;
-            already_occupied.push(pos);
-
-            // So that larger populations are more
-            // likely to have larger radii
-            let pop = grid[&pos].len() as f32;
-            let radius_weights: Vec<(usize, f32)> = radii.iter().map(|r| {
-                let v = pop/max_pop;
-                let t = radii.len();
-                if v >= (*r as f32/t as f32) {
-                    let d = (t - r).pow(3) + 1;
-                    (*r, v/d as f32)
-                } else {
-                    (*r, 0.)
-                }
-            }).collect();
-            // println!("pop {:?}", pop);
-            // println!("radius_weights {:?}", radius_weights);
-            let radius = radius_weights.choose_weighted(&mut rng, |item| item.1).unwrap().0;
-            publisher.radius = radius;
-            // println!("radius {:?}", radius);
+            place_publisher(publisher, &grid, &ref_grid, &mut already_occupied, rng);
         }
 
         // Distance to a Publisher is
@@ -154,29 +243,390 @@ impl Simulation {
             outboxes: outboxes,
             publishers: publishers,
             platforms: platforms,
+            fact_checkers: (0..conf.n_fact_checkers)
+                .map(|i| FactChecker::new(i, &conf.fact_checker))
+                .collect(),
             n_produced: 0,
             n_pitched: 0,
             n_published: 0,
             agent_platforms: agent_platforms,
+            cascades: BTreeMap::new(),
+            expired: ExpiredContent::default(),
+            expired_cascades: Vec::new(),
+            market_events: Vec::new(),
+            policies: Vec::new(),
+            n_policies: 0,
+            step: 0,
             advertising_tax: 0.,
             subsidy: 0.,
         }
     }
 
-    pub fn step(&mut self, conf: &SimulationConfig, mut rng: &mut StdRng) {
+    pub fn step(&mut self, conf: &SimulationConfig, mut rng: &mut ChaCha20Rng) {
         self.produce(&conf, &mut rng);
+        self.coordinate(conf, rng);
+        self.fact_check(conf, rng);
         self.consume(&conf, &mut rng);
+        self.rewire(conf, rng);
+        self.step += 1;
+        self.expire_content(conf);
+        self.demographics(conf, rng);
+        self.market(conf, rng);
+    }
+
+    // Publishers that stay below the bankruptcy budget
+    // shut down, and new ones may enter if there's unmet demand
+    // Platforms that can't cover their costs also shut down.
+    pub fn market(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
+        self.market_events.clear();
+        if conf.platform.shutdown_steps > 0 {
+            let mut failed = Vec::new();
+            for p in self.platforms.iter_mut().filter(|p| p.active) {
+                if p.budget < 0. {
+                    p.insolvent_steps += 1;
+                } else {
+                    p.insolvent_steps = 0;
+                }
+                if p.insolvent_steps >= conf.platform.shutdown_steps {
+                    failed.push(p.id);
+                }
+            }
+            for p_id in failed {
+                self.close_platform(p_id, rng);
+            }
+        }
+        if conf.publisher.bankruptcy_steps > 0 {
+            let mut bankrupt = Vec::new();
+            for p in self.publishers.iter_mut().filter(|p| p.active) {
+                if p.budget < conf.publisher.bankruptcy_budget {
+                    p.insolvent_steps += 1;
+                } else {
+                    p.insolvent_steps = 0;
+                }
+                if p.insolvent_steps >= conf.publisher.bankruptcy_steps {
+                    bankrupt.push(p.id);
+                }
+            }
+            for p_id in bankrupt {
+                self.close_publisher(p_id);
+            }
+        }
+        if conf.publisher.entry_rate > 0. {
+            let p_enter = conf.publisher.entry_rate * self.unmet_demand();
+            if rng.gen::<f32>() < p_enter {
+                self.add_publisher(conf, rng);
+            }
+        }
+    }
+
+    // Fraction of living Agents who
+    // aren't subscribed to any Publisher
+    pub fn unmet_demand(&self) -> f32 {
+        let population = self.population();
+        if population == 0 {
+            return 0.;
+        }
+        let unsubscribed = self.agents.iter()
+            .filter(|a| a.alive && a.subscriptions.is_empty())
+            .count();
+        unsubscribed as f32 / population as f32
+    }
+
+    // A new Publisher is placed like the initial ones,
+    // avoiding locations that already have an active Publisher
+    pub fn add_publisher(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> PublisherId {
+        let id = self.publishers.len();
+        let mut publisher = Publisher::new(id, conf, rng);
+        let mut occupied: Vec<Position> = self.publishers.iter()
+            .filter(|p| p.active)
+            .map(|p| p.location)
+            .unique()
+            .collect();
+        place_publisher(&mut publisher, &self.grid, &self.ref_grid, &mut occupied, rng);
+        self.publishers.push(publisher);
+        self.outboxes.push(Vec::new());
+
+        self.distances = compute_distances(
+            &self.ref_grid,
+            &self.publishers.iter()
+                .map(|p| (p.location, p.radius))
+                .collect());
+        for a in self.agents.iter_mut().filter(|a| a.alive) {
+            let dist = self.distances[&a.location][id];
+            a.relevancies.push(relevance_from_dist(dist));
+        }
+
+        self.market_events.push(MarketEvent::Entry(id));
+        id
+    }
+
+    // The Platform stays in `platforms` so ids
+    // remain stable. Its users are forced to
+    // move to another Platform.
+    pub fn close_platform(&mut self, id: PlatformId, rng: &mut ChaCha20Rng) {
+        if !self.platforms[id].active {
+            return;
+        }
+        let users = self.platforms[id].shut_down();
+        for a_id in &users {
+            self.agent_platforms[*a_id].remove(&id);
+            self.agents[*a_id].platform_value.remove(&id);
+        }
+        self.market_events.push(MarketEvent::PlatformExit(id));
+
+        for a_id in users {
+            if let Some(p_id) = self.migration_target(a_id, rng) {
+                self.join_platform(a_id, p_id);
+            }
+        }
+    }
+
+    // The active Platform most of the Agent's friends are on,
+    // or a random one if none of them are on one
+    fn migration_target(&self, a_id: AgentId, rng: &mut ChaCha20Rng) -> Option<PlatformId> {
+        let candidates: Vec<PlatformId> = self.platforms.iter()
+            .filter(|p| p.active && !self.agent_platforms[a_id].contains(&p.id))
+            .map(|p| p.id)
+            .collect();
+        let following = self.network.following_ids(&a_id);
+        let n_friends = |p_id: &PlatformId| following.iter()
+            .filter(|b_id| self.agent_platforms[**b_id].contains(p_id))
+            .count();
+        match candidates.iter().max_by_key(|p_id| n_friends(p_id)) {
+            Some(p_id) if n_friends(p_id) > 0 => Some(*p_id),
+            _ => candidates.choose(rng).cloned()
+        }
+    }
+
+    // Sign the Agent up and follow their friends there
+    // ENH: Maybe not all friends should be followed
+    pub fn join_platform(&mut self, a_id: AgentId, p_id: PlatformId) {
+        if !self.platforms[p_id].is_signed_up(&a_id) {
+            self.platforms[p_id].signup(a_id);
+            self.agent_platforms[a_id].insert(p_id);
+            for b_id in self.network.following_ids(&a_id) {
+                let platform = &mut self.platforms[p_id];
+                if platform.is_signed_up(b_id) {
+                    platform.follow(&a_id, b_id);
+                    platform.follow(b_id, &a_id);
+                }
+            }
+        }
+    }
+
+    pub fn leave_platform(&mut self, a_id: AgentId, p_id: PlatformId) {
+        self.platforms[p_id].remove_user(&a_id);
+        self.agent_platforms[a_id].remove(&p_id);
+        self.agents[a_id].platform_value.remove(&p_id);
+    }
+
+    // The Publisher stays in `publishers` so ids
+    // remain stable. Its subscribers are released
+    // and its content archive is left as is.
+    pub fn close_publisher(&mut self, id: PublisherId) {
+        let p = &mut self.publishers[id];
+        if !p.active {
+            return;
+        }
+        p.active = false;
+        p.subscribers = 0;
+        p.n_last_published = 0;
+        self.outboxes[id].clear();
+        for a in &mut self.agents {
+            a.subscriptions.remove(&id);
+        }
+        self.market_events.push(MarketEvent::Exit(id));
+    }
+
+    // FactCheckers label content that's
+    // in circulation and hasn't been checked yet
+    pub fn fact_check(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
+        if !self.fact_checkers.iter().any(|f| f.active) {
+            return;
+        }
+        // Only what's about to be read
+        // is worth checking
+        let mut seen: FnvHashSet<ContentId> = FnvHashSet::default();
+        let unlabeled: Vec<&Arc<Content>> = self.share_queues.iter().flatten()
+            .chain(self.outboxes.iter().flatten())
+            .map(|sc| &sc.content)
+            .filter(|c| c.label.get().is_none() && seen.insert(c.id))
+            .collect();
+        for f in self.fact_checkers.iter_mut().filter(|f| f.active) {
+            f.check(&unlabeled, &conf.fact_checker, rng);
+            f.budget += conf.fact_checker.budget;
+        }
+    }
+
+    // Agents leave and join according to the
+    // config's `death_rate` and `birth_rate`
+    pub fn demographics(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
+        let population = self.population() as u64;
+        if conf.death_rate > 0. {
+            let n = Binomial::new(population, conf.death_rate as f64).unwrap().sample(rng);
+            self.remove_agents(n as usize, rng);
+        }
+        if conf.birth_rate > 0. {
+            let n = Binomial::new(population, conf.birth_rate as f64).unwrap().sample(rng);
+            self.add_agents(n as usize, conf, rng);
+        }
+    }
+
+    // Ids are indices into the Simulation's Vecs,
+    // so Agents, Publishers, Platforms and FactCheckers
+    // that leave are never dropped, only marked as
+    // no longer alive/active. New ones always get ids
+    // following all existing (including removed) ones.
+    pub fn add_agents(&mut self, n: usize, conf: &SimulationConfig, rng: &mut ChaCha20Rng) -> Vec<AgentId> {
+        let start = self.agents.len();
+        let mut new_agents: Vec<Agent> = (start..start+n)
+            .map(|i| Agent::new(i, conf, rng))
+            .collect();
+
+        distribute_agents(&mut new_agents, &mut self.grid, rng);
+        set_agent_relevancies(&self.distances, &mut new_agents);
+
+        for a in new_agents {
+            self.agent_platforms.push(FnvHashSet::default());
+            self.share_queues.push(Vec::new());
+            self.agents.push(a);
+        }
+
+        self.network.preferential_attachment(&self.agents, MAX_FRIENDS, rng);
+        (start..start+n).collect()
+    }
+
+    // Remove randomly chosen living Agents
+    pub fn remove_agents(&mut self, n: usize, rng: &mut ChaCha20Rng) {
+        let living: Vec<AgentId> = self.agents.iter()
+            .filter(|a| a.alive)
+            .map(|a| a.id)
+            .collect();
+        let to_remove: Vec<AgentId> = living.choose_multiple(rng, n).cloned().collect();
+        if to_remove.is_empty() {
+            return;
+        }
+        for a_id in to_remove {
+            self.remove_agent(a_id);
+        }
+        self.forget_removed();
+    }
+
+    // The Agent stays in `agents` so ids
+    // remain stable, but is taken out of the networks,
+    // Platforms, Publishers' subscribers and the grid.
+    // What they kept is freed, so all that's left is an
+    // empty tombstone in `agents`, `share_queues` and
+    // `agent_platforms`, which grow by one for every
+    // Agent ever added. See also `forget_removed`.
+    pub fn remove_agent(&mut self, id: AgentId) {
+        let a = &mut self.agents[id];
+        if !a.alive {
+            return;
+        }
+        a.alive = false;
+
+        self.network.remove_node(&id);
+        for p_id in std::mem::take(&mut self.agent_platforms[id]) {
+            self.platforms[p_id].remove_user(&id);
+        }
+        for p_id in std::mem::take(&mut a.subscriptions) {
+            let p = &mut self.publishers[p_id];
+            p.subscribers = p.subscribers.saturating_sub(1);
+        }
+        self.share_queues[id] = Vec::new();
+        self.grid.get_mut(&a.location).unwrap().retain(|a_id| *a_id != id);
+
+        // No longer needed. Nothing is
+        // added to these once they're gone.
+        a.trust = FnvHashMap::default();
+        a.publishers = FnvHashMap::default();
+        a.publishabilities = FnvHashMap::default();
+        a.platform_value = FnvHashMap::default();
+        a.relevancies = Vec::new();
+        a.content = LimitedQueue::new(0);
+        a.seen_content = LimitedSet::new(0);
+    }
+
+    // Living Agents drop their trust of removed ones.
+    // No new trust is formed for them, see `Agent::consume`.
+    pub fn forget_removed(&mut self) {
+        let alive: Vec<bool> = self.agents.iter().map(|a| a.alive).collect();
+        self.agents.par_iter_mut().filter(|a| a.alive).for_each(|a| {
+            a.trust.retain(|id, _| alive[*id]);
+        });
+    }
+
+    // Number of living Agents
+    pub fn population(&self) -> usize {
+        self.agents.iter().filter(|a| a.alive).count()
+    }
+
+    // Drop Content according to the config's `content_expiry`,
+    // adding it to the expired totals. Its cascade is handed
+    // off for recording, see `expired_cascades`.
+    // Agents and Publishers may still hold on to it in their archives.
+    pub fn expire_content(&mut self, conf: &SimulationConfig) {
+        let step = self.step;
+        let cascades = &mut self.cascades;
+        let expired = &mut self.expired;
+        let expired_cascades = &mut self.expired_cascades;
+        expired_cascades.clear();
+        self.content.retain(|c| {
+            let keep = match conf.content_expiry {
+                ContentExpiry::Never => true,
+                ContentExpiry::Ttl(ttl) => match cascades.get(&c.id) {
+                    Some(cascade) => step - cascade.started() < ttl,
+                    None => true
+                },
+                // Only referenced here
+                ContentExpiry::Unreferenced => Arc::strong_count(c) > 1,
+            };
+            if !keep {
+                expired.add(c);
+                if let Some(cascade) = cascades.remove(&c.id) {
+                    expired.max_cascade_depth = expired.max_cascade_depth.max(cascade.depth());
+                    expired_cascades.push(ExpiredCascade {
+                        id: c.id,
+                        author: c.author,
+                        publisher: c.publisher,
+                        cascade,
+                    });
+                }
+            }
+            keep
+        });
     }
 
-    pub fn produce(&mut self, conf: &SimulationConfig, mut rng: &mut StdRng) {
+    pub fn produce(&mut self, conf: &SimulationConfig, mut rng: &mut ChaCha20Rng) {
         let mut n_pitched = 0;
         let mut n_published = 0;
         let mut new_content: FnvHashMap<(SharerType, usize), Vec<Content>> = FnvHashMap::default();
         for p in &mut self.publishers {
             p.n_ads_sold = 0.;
         }
-        let population = self.agents.len();
-        for mut a in &mut self.agents {
+        let population = self.population();
+        for mut a in self.agents.iter_mut().filter(|a| a.alive) {
+            // Bots always self-publish
+            if a.bot {
+                for body in a.produce_campaign(conf, rng) {
+                    let content = Content {
+                        id: new_content_id(rng),
+                        publisher: None,
+                        author: a.id,
+                        body,
+                        ads: a.ads,
+                        stats: ContentStats::default(),
+                        paywalled: false,
+                        label: Label::default(),
+                    };
+                    let val = new_content.entry((SharerType::Agent, a.id))
+                        .or_default();
+                    (*val).push(content);
+                }
+                a.update_reach();
+                continue;
+            }
             match a.try_produce(population, &conf, &mut rng) {
                 Some(body) => {
                     // People give up after not getting anything
@@ -186,6 +636,7 @@ impl Simulation {
                         n_pitched += 1;
                         // Decide to pitch to publisher
                         let publishers = self.publishers.iter()
+                            .filter(|p| p.active)
                             .map(|p| {
                                 let prob = a.publishabilities.entry(p.id).or_insert(1.).clone();
                                 // Publisher id, probability of acceptance, expected value
@@ -221,11 +672,14 @@ impl Simulation {
                         a.publishability = ewma(0., a.publishability);
 
                         let content = Content {
-                            id: ContentId::new_v4(),
+                            id: new_content_id(rng),
                             publisher: None,
                             author: a.id,
                             body: body,
                             ads: a.ads,
+                            stats: ContentStats::default(),
+                            paywalled: false,
+                            label: Label::default(),
                         };
                         let val = new_content.entry((SharerType::Agent, a.id))
                             .or_insert(Vec::new());
@@ -250,25 +704,36 @@ impl Simulation {
 
                 self.content.push(content.clone());
 
+                let mut cascade = match typ {
+                    SharerType::Publisher => Cascade::new((SharerType::Publisher, id), self.step),
+                    SharerType::Agent => Cascade::new((SharerType::Agent, content.author), self.step),
+                };
+
                 // TODO
                 let to_share = &mut self.share_queues[content.author];
                 to_share.push(SharedContent {
                     content: content.clone(),
-                    sharer: (SharerType::Agent, content.author)
+                    sharer: (SharerType::Agent, content.author),
+                    chain: Vec::new(),
+                    shared_at: self.step,
                 });
                 self.agents[content.author].content.push(content.clone());
                 match typ {
                     SharerType::Publisher => {
+                        cascade.add_share((SharerType::Agent, content.author), (SharerType::Publisher, id), Channel::Origin, self.step);
                         self.publishers[id].n_ads_sold += content.ads;
                         self.publishers[id].content.push(content.clone());
                         let to_share = &mut self.outboxes[id];
                         to_share.push(SharedContent {
                             content: content.clone(),
-                            sharer: (SharerType::Publisher, id)
+                            sharer: (SharerType::Publisher, id),
+                            chain: Vec::new(),
+                            shared_at: self.step,
                         });
                     },
                     SharerType::Agent => {}
                 }
+                self.cascades.insert(content.id, cascade);
             }
         }
 
@@ -277,76 +742,232 @@ impl Simulation {
         self.n_published = n_published;
     }
 
+    // Agents drop offline ties to people they've come
+    // to distrust, and may form new ones with people they're
+    // likely to run into: friends of friends and people nearby.
+    // New ties carry over to Platforms they're both on.
+    pub fn rewire(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
+        if conf.dissolve_trust > 0. {
+            for a in self.agents.iter().filter(|a| a.alive && !a.bot) {
+                let distrusted: Vec<AgentId> = self.network.following_ids(&a.id).iter()
+                    .filter(|b_id| a.trust.get(b_id).is_some_and(|t| *t < conf.dissolve_trust))
+                    .cloned()
+                    .collect();
+                for b_id in distrusted {
+                    self.network.remove_edges(&a.id, &b_id);
+
+                    // They stop following each other online too
+                    for p_id in self.agent_platforms[a.id].iter().sorted() {
+                        let platform = &mut self.platforms[*p_id];
+                        if platform.following_ids(&a.id).contains(&b_id) {
+                            platform.unfollow(&a.id, &b_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        if conf.rewire_rate <= 0. {
+            return;
+        }
+        for a_id in 0..self.agents.len() {
+            let a = &self.agents[a_id];
+            if !a.alive || a.bot || rng.gen::<f32>() >= conf.rewire_rate {
+                continue;
+            }
+            let following = self.network.following_ids(&a_id);
+            let candidates: Vec<AgentId> = following.iter()
+                .flat_map(|b_id| self.network.following_ids(b_id).iter())
+                .chain(self.grid[&a.location].iter())
+                .filter(|c_id| **c_id != a_id && !following.contains(c_id))
+                .filter(|c_id| self.agents[**c_id].alive && !self.agents[**c_id].bot)
+                .cloned()
+                .sorted()
+                .dedup()
+                .collect();
+            let c_id = match candidates.choose(rng) {
+                Some(c_id) => *c_id,
+                None => continue
+            };
+
+            // Probability they form a tie based on:
+            // 1. their similarity
+            // 2. the share of the Agent's friends who know them
+            // 3. if they are at the same location
+            let candidate = &self.agents[c_id];
+            let sim = a.similarity(candidate);
+            let mutual = if !following.is_empty() {
+                following.iter()
+                    .filter(|b_id| self.network.following_ids(b_id).contains(&c_id))
+                    .count() as f32 / following.len() as f32
+            } else {
+                0.
+            };
+            let same_location = if a.location == candidate.location { 1. } else { 0. };
+            let p = (sim + mutual + same_location) / 3.;
+            if rng.gen::<f32>() < p {
+                self.network.add_edge(&a_id, &c_id);
+                for p_id in self.agent_platforms[a_id].iter().sorted() {
+                    let platform = &mut self.platforms[*p_id];
+                    if platform.is_signed_up(&c_id) && !platform.following_ids(&a_id).contains(&c_id) {
+                        platform.follow(&a_id, &c_id);
+                    }
+                }
+            }
+        }
+    }
+
+    // Bots sign up to as many Platforms as they can,
+    // follow users there in bulk hoping they follow back,
+    // and reshare what the other bots just posted
+    pub fn coordinate(&mut self, conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
+        let bots: Vec<AgentId> = self.agents.iter()
+            .filter(|a| a.alive && a.bot)
+            .map(|a| a.id)
+            .collect();
+        if bots.is_empty() {
+            return;
+        }
+
+        let step = self.step;
+        for b_id in &bots {
+            let p_ids: Vec<PlatformId> = self.platforms.iter()
+                .filter(|p| p.active && !p.is_signed_up(b_id))
+                .map(|p| p.id)
+                .collect();
+            for p_id in p_ids {
+                if self.agent_platforms[*b_id].len() >= conf.max_platforms {
+                    break;
+                }
+                self.join_platform(*b_id, p_id);
+            }
+
+            for p_id in self.agent_platforms[*b_id].iter().sorted() {
+                let platform = &mut self.platforms[*p_id];
+                let following = platform.following_ids(b_id).clone();
+                let candidates: Vec<AgentId> = platform.users().into_iter()
+                    .filter(|a_id| a_id != b_id && !following.contains(a_id))
+                    .collect();
+                for a_id in candidates.choose_multiple(rng, conf.bot.follows) {
+                    platform.follow(b_id, a_id);
+                    if conf.bot.follow_back > 0. && rng.gen::<f32>() < conf.bot.follow_back {
+                        platform.follow(a_id, b_id);
+                    }
+                }
+            }
+        }
+
+        // What the bots posted this step
+        let posted: Vec<Arc<Content>> = bots.iter()
+            .flat_map(|b_id| self.share_queues[*b_id].iter()
+                .filter(move |sc| sc.shared_at == step && sc.chain.is_empty() && sc.content.author == *b_id))
+            .map(|sc| sc.content.clone())
+            .collect();
+        for b_id in &bots {
+            for content in posted.iter().filter(|c| c.author != *b_id) {
+                if conf.bot.amplify < 1. && rng.gen::<f32>() >= conf.bot.amplify {
+                    continue;
+                }
+                let from = (SharerType::Agent, content.author);
+                let sharer = (SharerType::Agent, *b_id);
+                content.stats.share();
+                if let Some(cascade) = self.cascades.get_mut(&content.id) {
+                    cascade.add_share(sharer, from, Channel::Coordinated, step);
+                }
+                self.share_queues[*b_id].push(SharedContent {
+                    content: content.clone(),
+                    sharer,
+                    chain: vec![from],
+                    shared_at: step,
+                });
+            }
+        }
+    }
+
     pub fn consume(&mut self,
                    conf: &SimulationConfig,
-                   mut rng: &mut StdRng) {
-        let mut sub_changes: Vec<isize> = vec![0; self.publishers.len()];
-
-        // Note the way these are added to assumes that agents are iterated
-        // over sequentially, i.e agent.id=0, agent.id=1, etc...
-        // and that each agent adds to the vec.
-        let mut new_to_share: Vec<Vec<SharedContent>> = Vec::with_capacity(self.agents.len());
-        let mut follow_changes: Vec<(FnvHashSet<AgentId>, FnvHashSet<AgentId>)> = Vec::with_capacity(self.agents.len());
+                   mut rng: &mut ChaCha20Rng) {
+        // Each Agent gets its own random stream for this step,
+        // so that results don't depend on how Agents are
+        // split across threads.
+        let step_seed: u64 = rng.gen();
+
+        // Platforms decide what shared content to act on,
+        // giving the chance each piece is still shown
+        let share_queues = &self.share_queues;
+        let moderation: Vec<FnvHashMap<ContentId, f32>> = self.platforms.iter_mut()
+            .map(|p| moderation::moderate(p, share_queues, &conf.platform, rng))
+            .collect();
 
-        let mut signups: FnvHashMap<AgentId, PlatformId> = FnvHashMap::default();
+        let step = self.step;
+        let network = &self.network;
+        let outboxes = &self.outboxes;
+        let agent_platforms = &self.agent_platforms;
+        let platforms = &self.platforms;
+        let removed: FnvHashSet<AgentId> = self.agents.iter()
+            .filter(|a| !a.alive)
+            .map(|a| a.id)
+            .collect();
 
-        let mut platforms: FnvHashMap<PlatformId, usize> = FnvHashMap::default();
-        let mut all_data: FnvHashMap<PlatformId, f32> = FnvHashMap::default();
-        let mut ad_revenue: FnvHashMap<(SharerType, usize), f32> = FnvHashMap::default();
+        // First decide what each Agent encounters.
+        // This is done before anyone consumes anything so that
+        // feeds don't depend on the content stats other Agents
+        // are updating at the same time.
+        let encounters: Vec<_> = self.agents.par_iter().map(|a| {
+            let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(step_seed);
+            rng.set_stream(a.id as u64);
 
-        // Hack to mutably iterate
-        let contact_rate = conf.contact_rate as f64;
-        let mut agents: Vec<Agent> = self.agents.drain(..).collect();
-        for mut a in agents.drain(..) {
             let mut to_read: Vec<(Option<&PlatformId>, &SharedContent)> = Vec::new();
-            // let to_read = &mut shared;
+            if !a.alive {
+                return (to_read, None, rng);
+            }
 
             // Agent encounters shared content
-            let following = self.network.following_ids(&a.id).clone();
+            let following = network.following_ids(&a.id);
 
             // "Offline" encounters
-            to_read.clear();
             let n = following.len() as u64;
-            let n_encounters = Binomial::new(n, contact_rate).unwrap().sample(rng);
+            let n_encounters = Binomial::new(n, conf.contact_rate as f64).unwrap().sample(&mut rng);
             to_read.extend(
-                following.choose_multiple(rng, n_encounters as usize)
-                .flat_map(|a_id| self.share_queues[*a_id].iter().map(|sc| (None, sc))));
+                following.choose_multiple(&mut rng, n_encounters as usize)
+                .flat_map(|a_id| share_queues[*a_id].iter().map(|sc| (None, sc))));
 
             // Subscribed publishers
             // ENH: Publishers on all platforms.
             // e.g. outbox.iter().flat_map(|sc| a.platforms.iter().map(|p_id| (p_id, sc.clone())))
             // Although maybe it's not worth the additional overhead?
-            to_read.extend(a.subscriptions.iter()
-                          .flat_map(|p_id| self.outboxes[*p_id].iter().map(|sc| (None, sc))));
+            // Iterated in id order so that the read order
+            // doesn't depend on the set's internal layout
+            to_read.extend(a.subscriptions.iter().sorted()
+                          .flat_map(|p_id| outboxes[*p_id].iter().map(|sc| (None, sc))));
 
             // Platforms
             // We basically assume that if someone shares something,
             // they share it across all platforms and increases the likelihood
             // that the Agent encounters that shared content.
-            // Unlike offline encounters, we roll per shared content
-            // rather than per agent.
+            // What they encounter on each is up to its FeedRanker.
             // ENH: Agents may develop a preference for a platform?
-            to_read.extend(self.agent_platforms[a.id].iter()
-                .flat_map(|p_id| self.platforms[*p_id].following_ids(&a.id).into_iter()
-                          .map(move |a_id| (p_id, a_id)))
-                .flat_map(|(p_id, a_id)| {
-                    // "Algorithmic" rating based on Agent's trust of Agent B.
-                    // ENH: Trust values should be platform-specific,
-                    // to capture that platforms have incomplete/noisy information about
-                    // "trust" between users.
-                    let contact_rate = f32::min(1., conf.contact_rate + match a.trust.get(a_id) {
-                        Some(v) => *v,
-                        None => 0.
-                    });
-
-                    let to_share = &self.share_queues[*a_id];
-                    let n_encounters = Binomial::new(
-                        to_share.len() as u64,
-                        contact_rate as f64).unwrap().sample(rng);
+            for p_id in agent_platforms[a.id].iter().sorted() {
+                let moderated = &moderation[*p_id];
+                to_read.extend(platforms[*p_id].feed(a, share_queues, conf, &mut rng)
+                    .into_iter()
+                    .filter(|sc| {
+                        // Platforms may also down-rank content labeled false
+                        let mut v = moderated.get(&sc.content.id).cloned().unwrap_or(1.);
+                        if sc.content.label.get() == Some(Verdict::False) {
+                            v *= conf.fact_checker.platform_visibility;
+                        }
+                        v >= 1. || (v > 0. && rng.gen::<f32>() < v)
+                    })
+                    .map(|sc| (Some(p_id), sc)));
+            }
 
-                    to_share.choose_multiple(rng, n_encounters as usize)
-                        .map(move |sc| (Some(p_id), sc))
-                    }));
+            // Content that's been around for a while
+            // is less likely to be read
+            to_read.retain(|(_, sc)| {
+                let age = sc.age(step);
+                age == 0 || rng.gen::<f32>() < conf.freshness_decay.powi(age as i32)
+            });
 
             // Avoid ordering bias
             to_read.shuffle(&mut rng);
@@ -354,18 +975,20 @@ impl Simulation {
 
             // Only consider signing up to new platforms
             // if Agent is not platform-saturated
-            if self.agent_platforms[a.id].len() < conf.max_platforms {
-                for p in &self.platforms {
-                    platforms.insert(p.id, 0);
+            let mut signup = None;
+            if agent_platforms[a.id].len() < conf.max_platforms {
+                let mut candidates: FnvHashMap<PlatformId, usize> = FnvHashMap::default();
+                for p in platforms.iter().filter(|p| p.active) {
+                    candidates.insert(p.id, 0);
                 }
 
                 // See what platforms friends are on
                 following.iter()
-                    .flat_map(|a_id| &self.agent_platforms[*a_id])
-                    .fold(&mut platforms, |acc, p_id| {
+                    .flat_map(|a_id| &agent_platforms[*a_id])
+                    .fold(&mut candidates, |acc, p_id| {
                         // Only consider platforms the agent
                         // isn't already signed up to
-                        if !self.agent_platforms[a.id].contains(p_id) {
+                        if !agent_platforms[a.id].contains(p_id) {
                             *(acc.entry(*p_id).or_insert(0)) += 1;
                         }
                         acc
@@ -373,75 +996,187 @@ impl Simulation {
 
                 // Get platform with most friends
                 // If no friends, choose a random one
-                if platforms.values().all(|v| *v == 0) {
-                    let p_ids: Vec<&PlatformId> = platforms.keys().collect();
+                if candidates.values().all(|v| *v == 0) {
+                    let p_ids: Vec<&PlatformId> = candidates.keys().collect();
                     let p_id = p_ids.choose(&mut rng);
                     match p_id {
                         Some(p_id) => {
                             let roll: f32 = rng.gen();
                             if roll < conf.base_signup_rate {
-                                signups.insert(a.id, **p_id);
+                                signup = Some(**p_id);
                             }
                         },
                         None => {}
                     }
                 } else {
-                    match platforms.iter().max_by_key(|&(_, v)| v) {
-                        Some((p_id, count)) => {
-                            let roll: f32 = rng.gen();
-                            if roll < (conf.base_signup_rate + (*count as f32)/(following.len() as f32)) {
-                                signups.insert(a.id, *p_id);
-                            }
-                        },
-                        None => {}
+                    if let Some((p_id, count)) = candidates.iter().max_by_key(|&(_, v)| v) {
+                        let roll: f32 = rng.gen();
+                        if roll < (conf.base_signup_rate + (*count as f32)/(following.len() as f32)) {
+                            signup = Some(*p_id);
+                        }
                     }
                 }
             }
 
-            let (will_share, (new_subs, unsubs), (follows, unfollows), data, revenue) = a.consume(&to_read, &conf, &mut rng);
+            (to_read, signup, rng)
+        }).collect();
+
+        // Results are collected in Agent order
+        let results: Vec<Consumption> = self.agents.par_iter_mut().zip(encounters).map(|(a, (to_read, signup, mut rng))| {
+            if !a.alive {
+                return Consumption::default();
+            }
+            let consumed = a.consume(&to_read, &removed, conf, &mut rng);
+            let will_share = &consumed.to_share;
+            let shared_ids: FnvHashSet<ContentId> = will_share.iter().map(|c| c.id).collect();
+            let observed: Vec<(PlatformId, AgentId, bool)> = to_read.iter()
+                .filter_map(|(p_id, sc)| p_id.map(|p_id| (*p_id, sc.sharer.1, shared_ids.contains(&sc.content.id))))
+                .collect();
+
+            // Agents leave Platforms where they don't
+            // engage with much or that their friends have left
+            let following = network.following_ids(&a.id);
+            let mut leaves = Vec::new();
+            for p_id in agent_platforms[a.id].iter().sorted() {
+                let seen: Vec<bool> = observed.iter()
+                    .filter(|(p, _, _)| p == p_id)
+                    .map(|(_, _, engaged)| *engaged)
+                    .collect();
+                let engagement = if !seen.is_empty() {
+                    seen.iter().filter(|e| **e).count() as f32 / seen.len() as f32
+                } else {
+                    0.
+                };
+
+                // Give new Platforms the benefit of the doubt
+                let value = a.platform_value.entry(*p_id).or_insert(1.);
+                *value = ewma(engagement, *value);
+
+                let friends = if !following.is_empty() {
+                    following.iter()
+                        .filter(|b_id| agent_platforms[**b_id].contains(p_id))
+                        .count() as f32 / following.len() as f32
+                } else {
+                    1.
+                };
+                // Bots stay put until they're caught
+                if !a.bot && (*value < conf.platform.leave_value || friends < conf.platform.leave_friends) {
+                    leaves.push(*p_id);
+                }
+            }
+            // Pass on content along with how it got to the Agent,
+            // i.e. the first of its encounters that they read.
+            // Reshares go out in the next step.
             let shareable = will_share.iter().map(|content| {
-                SharedContent {
-                    sharer: (SharerType::Agent, a.id),
-                    content: content.clone(),
+                match to_read.iter().find(|(_, sc)| sc.content.id == content.id) {
+                    Some((p_id, sc)) => {
+                        let channel = match (p_id, sc.sharer.0) {
+                            (Some(p_id), _) => Channel::Platform(**p_id),
+                            (None, SharerType::Publisher) => Channel::Subscription,
+                            (None, SharerType::Agent) => Channel::Offline,
+                        };
+                        (sc.reshare(a.id, step + 1), channel)
+                    },
+                    None => (SharedContent {
+                        sharer: (SharerType::Agent, a.id),
+                        chain: Vec::new(),
+                        shared_at: step + 1,
+                        content: content.clone(),
+                    }, Channel::Offline)
                 }
             }).collect();
+
+            Consumption {
+                to_share: shareable,
+                subs: consumed.subs,
+                follows: consumed.follows,
+                data: consumed.data,
+                revenue: consumed.revenue,
+                platform_revenue: consumed.platform_revenue,
+                signup,
+                leaves,
+                observed,
+            }
+        }).collect();
+
+        // Reduce Agents' results.
+        // This is done sequentially and in Agent order
+        // so that floating point sums are reproducible.
+        let mut sub_changes: Vec<isize> = vec![0; self.publishers.len()];
+        let mut new_to_share: Vec<Vec<SharedContent>> = Vec::with_capacity(self.agents.len());
+        let mut follow_changes: Vec<(FnvHashSet<AgentId>, FnvHashSet<AgentId>)> = Vec::with_capacity(self.agents.len());
+        let mut signups: FnvHashMap<AgentId, PlatformId> = FnvHashMap::default();
+        let mut leaves: Vec<(AgentId, PlatformId)> = Vec::new();
+        let mut all_data: FnvHashMap<PlatformId, f32> = FnvHashMap::default();
+        let mut ad_revenue: FnvHashMap<(SharerType, usize), f32> = FnvHashMap::default();
+        let mut platform_ad_revenue: FnvHashMap<PlatformId, f32> = FnvHashMap::default();
+        for (a_id, result) in results.into_iter().enumerate() {
+            let (new_subs, unsubs) = result.subs;
             for pub_id in new_subs {
-                sub_changes[pub_id] += 1;
+                // Can't subscribe to a Publisher that's shut down
+                // or that the Agent won't pay for
+                let p = &self.publishers[pub_id];
+                if p.active && self.agents[a_id].willing_to_pay(&pub_id, p.price, conf) {
+                    sub_changes[pub_id] += 1;
+                } else {
+                    self.agents[a_id].subscriptions.remove(&pub_id);
+                }
             }
             for pub_id in unsubs {
                 sub_changes[pub_id] -= 1;
             }
 
-            follow_changes.push((follows, unfollows));
+            follow_changes.push(result.follows);
 
             // Aggregate generated data
-            for (p_id, d) in data {
+            for (p_id, d) in result.data {
                 let d_ = all_data.entry(p_id).or_insert(0.);
                 *d_ += d;
             }
 
             // Aggregate ad revenue
-            for (tid, r) in revenue {
+            for (tid, r) in result.revenue {
                 let r_ = ad_revenue.entry(tid).or_insert(0.);
                 *r_ += r;
             }
+            for (p_id, r) in result.platform_revenue {
+                let r_ = platform_ad_revenue.entry(p_id).or_insert(0.);
+                *r_ += r;
+            }
 
-            new_to_share.push(shareable);
-            self.agents.push(a);
+            if let Some(p_id) = result.signup {
+                signups.insert(a_id, p_id);
+            }
+            leaves.extend(result.leaves.into_iter().map(|p_id| (a_id, p_id)));
+
+            // Platforms update their trust estimates
+            for (p_id, b_id, engaged) in result.observed {
+                self.platforms[p_id].observe(a_id, b_id, engaged, conf.default_trust, conf.platform.trust_noise, rng);
+            }
+
+            let mut to_share = Vec::with_capacity(result.to_share.len());
+            for (sc, channel) in result.to_share {
+                if let (Some(cascade), Some(from)) = (self.cascades.get_mut(&sc.content.id), sc.chain.last()) {
+                    cascade.add_share(sc.sharer, *from, channel, self.step);
+                }
+                to_share.push(sc);
+            }
+            new_to_share.push(to_share);
         }
 
-        // Update share lists
+        // Update share lists, keeping what's
+        // still within the share window next step
         for (a_id, mut to_share_) in new_to_share.into_iter().enumerate() {
             let to_share = &mut self.share_queues[a_id];
-            to_share.clear();
+            to_share.retain(|sc| sc.age(step) + 1 < conf.share_window);
             to_share.append(&mut to_share_);
         }
 
         // Update follows
         // TODO this feels very messy
         for (a_id, (follows, unfollows)) in follow_changes.iter().enumerate() {
-            if follows.len() > 0 || unfollows.len() > 0 {
-                let p_ids: Vec<&PlatformId> = self.agent_platforms[a_id].iter().collect();
+            if !follows.is_empty() || !unfollows.is_empty() {
+                let p_ids: Vec<&PlatformId> = self.agent_platforms[a_id].iter().sorted().collect();
                 for p_id in p_ids {
                     let pfrm = &mut self.platforms[*p_id];
                     for b_id in follows {
@@ -458,30 +1193,59 @@ impl Simulation {
             }
         }
 
+        // Subscribers pay each step, and drop
+        // subscriptions they can no longer afford
         for p in &mut self.publishers {
+            p.subscription_revenue = 0.;
+        }
+        for a in self.agents.iter_mut().filter(|a| a.alive) {
+            let subscriptions: Vec<PublisherId> = a.subscriptions.iter().sorted().cloned().collect();
+            for pub_id in subscriptions {
+                let p = &mut self.publishers[pub_id];
+                if p.price <= 0. {
+                    continue;
+                }
+                if a.resources >= p.price {
+                    a.resources -= p.price;
+                    p.subscription_revenue += p.price;
+                } else {
+                    a.subscriptions.remove(&pub_id);
+                    sub_changes[pub_id] -= 1;
+                }
+            }
+        }
+
+        for p in self.publishers.iter_mut().filter(|p| p.active) {
             p.audience_survey(conf.content_sample_size);
             p.update_reach();
 
             // Update subscribers
             p.subscribers = std::cmp::max(0, p.subscribers as isize + sub_changes[p.id]) as usize;
 
-            p.n_last_published = self.outboxes[p.id].len();
-            p.budget += p.regular_revenue() + self.subsidy;
+            p.n_last_published = self.outboxes[p.id].iter()
+                .filter(|sc| sc.shared_at == step).count();
+            p.budget += p.regular_revenue() + p.subscription_revenue + self.subsidy;
 
-            // ENH: Publisher pushes content
-            // for multiple steps?
-            self.outboxes[p.id].clear();
+            // Publishers push content for
+            // as many steps as the outbox window
+            self.outboxes[p.id].retain(|sc| sc.age(step) + 1 < conf.outbox_window);
         }
 
         // Distribute ad revenue
+        let mut learned: FnvHashSet<PublisherId> = FnvHashSet::default();
         for ((typ, id), r) in ad_revenue {
             let update = rng.gen::<f32>() < 0.1;
             let r = r * (1.-self.advertising_tax);
             match typ {
                 SharerType::Publisher => {
+                    if !self.publishers[id].active {
+                        continue;
+                    }
                     self.publishers[id].budget += r;
-                    self.publishers[id].learn(r, update, &mut rng);
+                    let revenue = r + self.publishers[id].subscription_revenue;
+                    self.publishers[id].learn(revenue, update, rng);
                     self.publishers[id].expenses = 0.;
+                    learned.insert(id);
                 },
                 SharerType::Agent => {
                     self.agents[id].resources += r;
@@ -491,27 +1255,107 @@ impl Simulation {
             }
         }
 
+        // Publishers without ad revenue still learn what to
+        // charge and how much to check from their subscriptions
+        for p in self.publishers.iter_mut().filter(|p| p.active && p.learns_price_or_checking() && !learned.contains(&p.id)) {
+            let update = rng.gen::<f32>() < 0.1;
+            let revenue = p.subscription_revenue;
+            p.learn(revenue, update, rng);
+            p.expenses = 0.;
+        }
+
         // Add data to platforms
-        for p in &mut self.platforms {
-            p.data += *all_data.entry(p.id).or_insert(0.);
+        for p in self.platforms.iter_mut().filter(|p| p.active) {
+            let data = all_data.get(&p.id).copied().unwrap_or(0.);
+            p.data += data;
             p.update_conversion_rate(conf.max_conversion_rate);
+
+            // Every piece of content consumed
+            // generates the same amount of data
+            let impressions = if conf.data_per_consume > 0. { data / conf.data_per_consume } else { 0. };
+            let ad_cut_revenue = platform_ad_revenue.get(&p.id).copied().unwrap_or(0.);
+            p.earn(impressions, ad_cut_revenue, conf.revenue_per_ad, conf.platform.operating_cost);
         }
 
         // Sign up agents and follow friends
-        // ENH: Maybe not all friends should be followed
         for (a_id, p_id) in signups {
-            if !self.platforms[p_id].is_signed_up(&a_id) {
-                self.platforms[p_id].signup(self.agents[a_id].id);
-                self.agent_platforms[a_id].insert(p_id);
-                for b_id in self.network.following_ids(&self.agents[a_id].id) {
-                    let platform = &mut self.platforms[p_id];
-                    if platform.is_signed_up(b_id) {
-                        platform.follow(&a_id, b_id);
-                        platform.follow(b_id, &a_id);
-                    }
-                }
-            }
+            self.join_platform(a_id, p_id);
         }
+
+        for (a_id, p_id) in leaves {
+            self.leave_platform(a_id, p_id);
+        }
+    }
+
+    pub fn content(&self) -> &[Arc<Content>] {
+        &self.content
+    }
+
+    // Content each Agent will share in the next step,
+    // indexed by AgentId
+    pub fn share_queues(&self) -> &[Vec<SharedContent>] {
+        &self.share_queues
+    }
+
+    // Content each Publisher will publish in the next step,
+    // indexed by PublisherId
+    pub fn outboxes(&self) -> &[Vec<SharedContent>] {
+        &self.outboxes
+    }
+
+    // Platforms each Agent is on, indexed by AgentId
+    pub fn agent_platforms(&self) -> &[FnvHashSet<PlatformId>] {
+        &self.agent_platforms
+    }
+
+    pub fn cascade(&self, id: &ContentId) -> Option<&Cascade> {
+        self.cascades.get(id)
+    }
+
+    // How each piece of content has spread
+    pub fn cascades(&self) -> &BTreeMap<ContentId, Cascade> {
+        &self.cascades
+    }
+
+    // Totals for Content that has been dropped
+    pub fn expired(&self) -> &ExpiredContent {
+        &self.expired
+    }
+
+    // Cascades of Content dropped in the last step
+    pub fn expired_cascades(&self) -> &[ExpiredCascade] {
+        &self.expired_cascades
+    }
+
+    // Publishers that entered or exited in the last step
+    pub fn market_events(&self) -> &[MarketEvent] {
+        &self.market_events
+    }
+
+    // Steps run so far
+    pub fn n_steps(&self) -> usize {
+        self.step
+    }
+
+    pub fn advertising_tax(&self) -> f32 {
+        self.advertising_tax
+    }
+
+    pub fn subsidy(&self) -> f32 {
+        self.subsidy
+    }
+
+    // All content currently referenced by the simulation,
+    // without duplicates.
+    pub fn referenced_content(&self) -> Vec<&Arc<Content>> {
+        let mut seen: FnvHashSet<ContentId> = FnvHashSet::default();
+        self.content.iter()
+            .chain(self.share_queues.iter().flatten().map(|sc| &sc.content))
+            .chain(self.outboxes.iter().flatten().map(|sc| &sc.content))
+            .chain(self.agents.iter().flat_map(|a| a.content.iter()))
+            .chain(self.publishers.iter().flat_map(|p| p.content.iter()))
+            .filter(|c| seen.insert(c.id))
+            .collect()
     }
 
     pub fn n_will_share(&self) -> usize {
@@ -519,63 +1363,223 @@ impl Simulation {
     }
 
     pub fn n_shares(&self) -> Vec<usize> {
-        self.content.iter().map(|c| Arc::strong_count(c)).collect()
+        self.content.iter().map(|c| c.stats.shares()).collect()
     }
 
     pub fn content_by_popularity(&self) -> std::vec::IntoIter<&Arc<Content>> {
-        self.content.iter().sorted_by(|a, b| Arc::strong_count(b).cmp(&Arc::strong_count(a)))
+        self.content.iter().sorted_by(|a, b| b.stats.shares().cmp(&a.stats.shares()))
+    }
+
+    pub fn active_policies(&self) -> &[PolicyRecord] {
+        &self.policies
+    }
+
+    // Apply a Policy that stays in effect
+    // until it's reverted
+    pub fn apply_policy(&mut self, policy: &Policy, conf: &mut SimulationConfig, rng: &mut ChaCha20Rng) -> PolicyId {
+        self.apply_policy_for(policy, None, conf, rng)
     }
 
-    pub fn apply_policy(&mut self, policy: &Policy, conf: &mut SimulationConfig, rng: &mut StdRng) {
-        match policy {
+    // Apply a Policy, reverting it automatically
+    // after `duration` steps if one is given.
+    // Panics if a duration is given for a Policy
+    // that can't be reverted, see `Policy::is_reversible`.
+    pub fn apply_policy_for(&mut self, policy: &Policy, duration: Option<usize>, conf: &mut SimulationConfig, rng: &mut ChaCha20Rng) -> PolicyId {
+        assert!(duration.is_none() || policy.is_reversible(), "policy {:?} can't be reverted, so it can't have a duration", policy);
+        let id = self.n_policies;
+        self.n_policies += 1;
+        let undo = match policy {
             Policy::Recession(n) => {
+                let (economy, unsubscribe_lag) = (conf.economy, conf.unsubscribe_lag);
+                let (unsubscribe_trust, subscribe_trust) = (conf.unsubscribe_trust, conf.subscribe_trust);
+                let (base_conversion_rate, revenue_per_ad) = (conf.base_conversion_rate, conf.revenue_per_ad);
                 conf.economy = f32::max(0., conf.economy - n);
                 conf.unsubscribe_lag = max(0, conf.unsubscribe_lag as isize - (*n*10.) as isize) as usize;
                 conf.unsubscribe_trust = f32::min(1., conf.unsubscribe_trust + n/5.);
                 conf.subscribe_trust = f32::min(1., conf.subscribe_trust + n/5.);
                 conf.base_conversion_rate = f32::max(0., conf.base_conversion_rate - n/100.);
                 conf.revenue_per_ad = f32::max(0., conf.revenue_per_ad - n/100.);
+                Undo::Recession {
+                    economy: economy - conf.economy,
+                    unsubscribe_lag: unsubscribe_lag - conf.unsubscribe_lag,
+                    unsubscribe_trust: conf.unsubscribe_trust - unsubscribe_trust,
+                    subscribe_trust: conf.subscribe_trust - subscribe_trust,
+                    base_conversion_rate: base_conversion_rate - conf.base_conversion_rate,
+                    revenue_per_ad: revenue_per_ad - conf.revenue_per_ad,
+                }
             },
 
             Policy::MediaLiteracy(n) => {
-                for a in &mut self.agents {
+                let mut raised = Vec::new();
+                for a in self.agents.iter_mut().filter(|a| a.alive) {
+                    let prev = a.media_literacy;
                     a.media_literacy = f32::min(1., a.media_literacy + n);
+                    raised.push((a.id, a.media_literacy - prev));
                 }
+                Undo::MediaLiteracy(raised)
             },
 
             Policy::FoundPlatforms(n) => {
+                let mut founded = Vec::new();
                 for _ in 0..*n {
-                    let platform = Platform::new(self.platforms.len());
+                    let platform = new_platform(self.platforms.len(), conf.platform.ranker, conf);
+                    founded.push(platform.id);
                     self.platforms.push(platform);
                 }
+                Undo::FoundPlatforms(founded)
             },
 
             Policy::TaxAdvertising(tax) => {
+                let prev = self.advertising_tax;
                 self.advertising_tax = *tax;
+                Undo::TaxAdvertising(prev)
             },
             Policy::SubsidizeProduction(amount) => {
+                let prev = self.subsidy;
                 self.subsidy = *amount;
+                Undo::SubsidizeProduction(prev)
             },
 
             Policy::PopulationChange(n) => {
                 if *n > 0 {
-                    let mut new_agents: Vec<Agent> = (0..(*n as usize))
-                        .map(|i| Agent::new(i, &conf.agent, rng))
-                        .collect();
+                    Undo::AddedAgents(self.add_agents(*n as usize, conf, rng))
+                } else if *n < 0 {
+                    let n = min(n.unsigned_abs(), self.population());
+                    self.remove_agents(n, rng);
+                    Undo::RemovedAgents(n)
+                } else {
+                    Undo::AddedAgents(Vec::new())
+                }
+            },
 
-                    distribute_agents(&mut new_agents, &mut self.grid, rng);
-                    set_agent_relevancies(&self.distances, &mut new_agents);
+            Policy::ModerateExtremeValues(threshold, visibility) => {
+                self.add_moderation(Rule { flag: Flag::ExtremeValues, threshold: *threshold, visibility: *visibility, policy: Some(id) }, conf)
+            },
+            Policy::ModerateSensationalism(threshold, visibility) => {
+                self.add_moderation(Rule { flag: Flag::Sensationalism, threshold: *threshold, visibility: *visibility, policy: Some(id) }, conf)
+            },
+            Policy::ModerateLowTrust(threshold, visibility) => {
+                self.add_moderation(Rule { flag: Flag::LowTrust, threshold: *threshold, visibility: *visibility, policy: Some(id) }, conf)
+            },
+            Policy::ModerateBots(threshold, visibility) => {
+                self.add_moderation(Rule { flag: Flag::Bots, threshold: *threshold, visibility: *visibility, policy: Some(id) }, conf)
+            },
 
-                    for a in new_agents {
-                        self.agent_platforms.push(FnvHashSet::default());
-                        self.share_queues.push(Vec::new());
-                        self.agents.push(a);
-                    }
+            Policy::FoundFactCheckers(n) => {
+                let mut founded = Vec::new();
+                for _ in 0..*n {
+                    let checker = FactChecker::new(self.fact_checkers.len(), &conf.fact_checker);
+                    founded.push(checker.id);
+                    self.fact_checkers.push(checker);
+                }
+                Undo::FoundFactCheckers(founded)
+            }
+        };
 
-                    self.network.preferential_attachment(&self.agents, MAX_FRIENDS, rng);
+        self.policies.push(PolicyRecord {
+            id,
+            policy: policy.clone(),
+            applied: self.step,
+            until: duration.map(|d| self.step + d),
+            undo,
+        });
+        id
+    }
+
+    // Take back a Policy that's still in effect.
+    // Returns false if there's no such Policy,
+    // or if it can't be reverted.
+    pub fn revert_policy(&mut self, id: PolicyId, conf: &mut SimulationConfig, rng: &mut ChaCha20Rng) -> bool {
+        let idx = match self.policies.iter().position(|p| p.id == id && p.policy.is_reversible()) {
+            Some(idx) => idx,
+            None => return false
+        };
+        let record = self.policies.remove(idx);
+        match record.undo {
+            Undo::Recession { economy, unsubscribe_lag, unsubscribe_trust, subscribe_trust, base_conversion_rate, revenue_per_ad } => {
+                conf.economy += economy;
+                conf.unsubscribe_lag += unsubscribe_lag;
+                conf.unsubscribe_trust = f32::max(0., conf.unsubscribe_trust - unsubscribe_trust);
+                conf.subscribe_trust = f32::max(0., conf.subscribe_trust - subscribe_trust);
+                conf.base_conversion_rate += base_conversion_rate;
+                conf.revenue_per_ad += revenue_per_ad;
+            },
+            Undo::MediaLiteracy(raised) => {
+                for (a_id, n) in raised {
+                    let a = &mut self.agents[a_id];
+                    a.media_literacy = f32::max(0., a.media_literacy - n);
+                }
+            },
+            Undo::FoundPlatforms(founded) => {
+                for p_id in founded {
+                    self.close_platform(p_id, rng);
+                }
+            },
+            // If a later Policy of the same kind is still in effect
+            // it keeps its value, and hands this one's `prev` on
+            // for when it's reverted in turn
+            Undo::TaxAdvertising(prev) => {
+                let later = self.policies[idx..].iter_mut()
+                    .find_map(|p| match &mut p.undo { Undo::TaxAdvertising(later) => Some(later), _ => None });
+                match later {
+                    Some(later) => *later = prev,
+                    None => self.advertising_tax = prev
+                }
+            },
+            Undo::SubsidizeProduction(prev) => {
+                let later = self.policies[idx..].iter_mut()
+                    .find_map(|p| match &mut p.undo { Undo::SubsidizeProduction(later) => Some(later), _ => None });
+                match later {
+                    Some(later) => *later = prev,
+                    None => self.subsidy = prev
+                }
+            },
+            Undo::AddedAgents(added) => {
+                for a_id in added {
+                    self.remove_agent(a_id);
+                }
+                self.forget_removed();
+            },
+            // Not reversible, see above
+            Undo::RemovedAgents(_) => {},
+            Undo::FoundFactCheckers(founded) => {
+                for f_id in founded {
+                    self.fact_checkers[f_id].active = false;
+                }
+            },
+            // Only the Rule this Policy added is removed,
+            // not any equal ones from the config or other Policies
+            Undo::Moderation(rule) => {
+                conf.platform.moderation.retain(|r| r.policy != rule.policy);
+                for p in &mut self.platforms {
+                    p.moderation.retain(|r| r.policy != rule.policy);
                 }
             }
         }
+        true
+    }
+
+    // Platforms, including ones founded later,
+    // moderate content by the Rule
+    fn add_moderation(&mut self, rule: Rule, conf: &mut SimulationConfig) -> Undo {
+        conf.platform.moderation.push(rule);
+        for p in self.platforms.iter_mut().filter(|p| p.active) {
+            p.moderation.push(rule);
+        }
+        Undo::Moderation(rule)
+    }
+
+    // Revert Policies whose duration is up,
+    // to be called before each step is run
+    pub fn expire_policies(&mut self, conf: &mut SimulationConfig, rng: &mut ChaCha20Rng) {
+        let step = self.step;
+        let expired: Vec<PolicyId> = self.policies.iter()
+            .filter(|p| p.until.is_some_and(|until| until <= step))
+            .map(|p| p.id)
+            .collect();
+        for id in expired {
+            self.revert_policy(id, conf, rng);
+        }
     }
 }
 
@@ -610,7 +1614,50 @@ pub fn set_agent_relevancies(distances: &FnvHashMap<Position, Vec<usize>>, agent
     }
 }
 
-fn distribute_agents(agents: &mut Vec<Agent>, grid: &mut FnvHashMap<Position, Vec<AgentId>>, rng: &mut StdRng) {
+fn new_platform(id: PlatformId, ranker: Ranker, conf: &SimulationConfig) -> Platform {
+    let mut platform = Platform::new(id, ranker);
+    platform.budget = conf.platform.base_budget;
+    platform.moderation = conf.platform.moderation.clone();
+    platform
+}
+
+// Assign a Publisher a location by population density,
+// avoiding already occupied locations, and a radius
+fn place_publisher(publisher: &mut Publisher, grid: &BTreeMap<Position, Vec<AgentId>>, ref_grid: &HexGrid, already_occupied: &mut Vec<Position>, mut rng: &mut ChaCha20Rng) {
+    let radii: Vec<usize> = (0..ref_grid.rows.max(ref_grid.cols)).collect();
+    let max_pop = grid.iter().fold(0, |acc, (_, agents)| agents.len().max(acc)) as f32;
+
+    // If all populated locations have a Publisher,
+    // reset to allow for multiple Publishers per location.
+    if grid.iter().all(|(pos, agents)| agents.is_empty() || already_occupied.contains(pos)) {
+        already_occupied.clear();
+    }
+    let weights: Vec<(Position, usize)> = grid.iter()
+        .filter(|(pos, agents)| !already_occupied.contains(pos) && !agents.is_empty())
+        .map(|(pos, agents)| (*pos, agents.len().pow(2)))
+        .collect();
+    let pos = weights.choose_weighted(&mut rng, |item| item.1).unwrap().0;
+    publisher.location = pos;
+    already_occupied.push(pos);
+
+    // So that larger populations are more
+    // likely to have larger radii
+    let pop = grid[&pos].len() as f32;
+    let radius_weights: Vec<(usize, f32)> = radii.iter().map(|r| {
+        let v = pop/max_pop;
+        let t = radii.len();
+        if v >= (*r as f32/t as f32) {
+            let d = (t - r).pow(3) + 1;
+            (*r, v/d as f32)
+        } else {
+            (*r, 0.)
+        }
+    }).collect();
+    let radius = radius_weights.choose_weighted(&mut rng, |item| item.1).unwrap().0;
+    publisher.radius = radius;
+}
+
+fn distribute_agents<'a, I: IntoIterator<Item=&'a mut Agent>>(agents: I, grid: &mut BTreeMap<Position, Vec<AgentId>>, rng: &mut ChaCha20Rng) {
     // Randomly assign agents by density
     for agent in agents {
         let weights: Vec<(Position, usize)> = grid.iter()
@@ -622,7 +1669,7 @@ fn distribute_agents(agents: &mut Vec<Agent>, grid: &mut FnvHashMap<Position, Ve
     }
 }
 
-pub fn ad_market(content: &mut FnvHashMap<(SharerType, usize), Vec<Content>>, agents: &Vec<Agent>, publishers: &Vec<Publisher>, platforms: &Vec<Platform>, conf: &SimulationConfig, rng: &mut StdRng) {
+pub fn ad_market(content: &mut FnvHashMap<(SharerType, usize), Vec<Content>>, agents: &[Agent], publishers: &[Publisher], platforms: &[Platform], conf: &SimulationConfig, rng: &mut ChaCha20Rng) {
     let econ = f32::min(conf.economy, 1.);
     let z = platforms.iter().fold(0., |acc, platform| acc + platform.conversion_rate);
     let max_p = 0.95; // Required to avoid beta of 0.0
@@ -662,6 +1709,10 @@ pub fn ad_market(content: &mut FnvHashMap<(SharerType, usize), Vec<Content>>, ag
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::config::{SinglePlatformConfig, NetworkFile};
+    use super::super::publisher::Pricing;
+    use super::super::feed::Ranker;
+    use super::super::testing::{test_conf, test_sim};
 
     #[test]
     fn distances() {
@@ -682,6 +1733,610 @@ mod tests {
         assert_eq!(distances[&(1, 2)], vec![2, 1]);
     }
 
+    #[test]
+    fn consume_independent_of_threads() {
+        let mut conf = test_conf();
+        conf.n_platforms = 2;
+        conf.base_signup_rate = 0.1;
+
+        // Engagement ranking reads content stats
+        conf.platforms.push(SinglePlatformConfig {
+            ranker: Ranker::Engagement
+        });
+        let run = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let (mut sim, mut rng) = test_sim(&conf);
+                for _ in 0..5 {
+                    sim.step(&conf, &mut rng);
+                }
+                let values: Vec<f32> = sim.agents.iter().flat_map(|a| a.values.iter().cloned().collect::<Vec<f32>>()).collect();
+                let budgets: Vec<f32> = sim.publishers.iter().map(|p| p.budget).collect();
+                (values, budgets, sim.n_shares())
+            })
+        };
+        assert_eq!(run(1), run(4));
+    }
+
+    #[test]
+    fn content_expiry() {
+        let mut conf = test_conf();
+        conf.content_expiry = ContentExpiry::Ttl(3);
+        let (mut sim, mut rng) = test_sim(&conf);
+        let mut n_expired_cascades = 0;
+        for _ in 0..10 {
+            sim.step(&conf, &mut rng);
+            n_expired_cascades += sim.expired_cascades().len();
+        }
+        assert!(sim.expired().n > 0);
+        assert_eq!(sim.content().len(), sim.cascades().len());
+
+        // Every expired cascade is handed off once
+        assert_eq!(n_expired_cascades, sim.expired().n);
+        for c in sim.content() {
+            assert!(sim.n_steps() - sim.cascade(&c.id).unwrap().started() < 3);
+        }
+
+        conf.content_expiry = ContentExpiry::Unreferenced;
+        sim.step(&conf, &mut rng);
+        assert!(sim.content().iter().all(|c| Arc::strong_count(c) > 1));
+    }
+
+    #[test]
+    fn content_windows() {
+        let mut conf = test_conf();
+        conf.outbox_window = 3;
+        conf.share_window = 2;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..10 {
+            sim.step(&conf, &mut rng);
+        }
+
+        // Ages as of the next step, which will be
+        // the last step they can be read in at most
+        let step = sim.n_steps();
+        let outbox_ages: Vec<usize> = sim.outboxes().iter().flatten().map(|sc| sc.age(step)).collect();
+        let share_ages: Vec<usize> = sim.share_queues().iter().flatten().map(|sc| sc.age(step)).collect();
+        assert!(outbox_ages.iter().all(|age| *age >= 1 && *age <= 2));
+        assert!(outbox_ages.contains(&2));
+        assert!(!share_ages.is_empty());
+        assert!(share_ages.iter().all(|age| *age <= 1));
+        assert!(share_ages.contains(&0));
+        assert!(share_ages.contains(&1));
+
+        // With the default windows everything is
+        // read fresh, so none of it decays
+        conf.outbox_window = 1;
+        conf.share_window = 1;
+        sim.step(&conf, &mut rng);
+        let step = sim.n_steps();
+        assert!(sim.outboxes().iter().flatten().all(|sc| sc.age(step) == 0));
+        assert!(sim.share_queues().iter().flatten().all(|sc| sc.age(step) == 0));
+    }
+
+    #[test]
+    fn churn() {
+        let mut conf = test_conf();
+        conf.n_platforms = 2;
+        conf.base_signup_rate = 0.5;
+        conf.birth_rate = 0.05;
+        conf.death_rate = 0.05;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..10 {
+            sim.step(&conf, &mut rng);
+        }
+        assert!(sim.agents.len() > 200);
+        assert!(sim.population() < sim.agents.len());
+
+        // Ids stay unique and index into `agents`
+        for (i, a) in sim.agents.iter().enumerate() {
+            assert_eq!(a.id, i);
+        }
+        assert_eq!(sim.share_queues().len(), sim.agents.len());
+        assert_eq!(sim.agent_platforms().len(), sim.agents.len());
+
+        // Removed Agents are gone from everywhere
+        let n_subscribers: usize = sim.agents.iter()
+            .filter(|a| a.alive)
+            .map(|a| a.subscriptions.len())
+            .sum();
+        assert_eq!(sim.publishers.iter().map(|p| p.subscribers).sum::<usize>(), n_subscribers);
+        assert_eq!(sim.grid.values().map(|a_ids| a_ids.len()).sum::<usize>(), sim.population());
+        assert_eq!(sim.network.n_nodes(), sim.population());
+        for a in sim.agents.iter().filter(|a| !a.alive) {
+            assert!(!sim.network.exists(&a.id));
+            assert!(sim.agent_platforms()[a.id].is_empty());
+            assert!(sim.share_queues()[a.id].is_empty());
+            assert!(a.subscriptions.is_empty());
+            assert!(a.trust.is_empty() && a.content.len() == 0);
+            for p in &sim.platforms {
+                assert!(!p.is_signed_up(&a.id));
+            }
+        }
+        for b_id in sim.network.nodes() {
+            assert!(sim.network.following_ids(&b_id).iter().all(|id| sim.agents[*id].alive));
+            assert!(sim.agents[b_id].trust.keys().all(|id| sim.agents[*id].alive));
+        }
+        for p in &sim.platforms {
+            for b_id in p.users() {
+                assert!(sim.agents[b_id].alive);
+                assert!(p.following_ids(&b_id).iter().all(|id| sim.agents[*id].alive));
+            }
+        }
+    }
+
+    #[test]
+    fn removed_not_trusted() {
+        let conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+
+        // Remove the most trusted Agent
+        let id = (0..sim.agents.len())
+            .max_by_key(|id| sim.agents.iter().filter(|a| a.trust.contains_key(id)).count())
+            .unwrap();
+        assert!(sim.agents.iter().any(|a| a.trust.contains_key(&id)));
+        sim.remove_agent(id);
+        sim.forget_removed();
+
+        // What they wrote and shared is still
+        // read, but they aren't trusted again
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+            assert!(sim.agents.iter().filter(|a| a.alive).all(|a| !a.trust.contains_key(&id)));
+        }
+    }
+
+    #[test]
+    fn population_change() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+
+        sim.apply_policy(&Policy::PopulationChange(50), &mut conf, &mut rng);
+        assert_eq!(sim.population(), 250);
+        assert!(sim.agents.iter().enumerate().all(|(i, a)| a.id == i));
+
+        let shrunk = sim.apply_policy(&Policy::PopulationChange(-100), &mut conf, &mut rng);
+        assert_eq!(sim.population(), 150);
+        assert_eq!(sim.agents.len(), 250);
+        assert_eq!(sim.network.n_nodes(), 150);
+        sim.step(&conf, &mut rng);
+
+        // Removals can't be taken back
+        assert!(!sim.revert_policy(shrunk, &mut conf, &mut rng));
+        assert_eq!(sim.population(), 150);
+        assert_eq!(sim.active_policies().len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn timed_population_decline() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+        sim.apply_policy_for(&Policy::PopulationChange(-100), Some(10), &mut conf, &mut rng);
+    }
+
+    #[test]
+    fn revert_policies() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+        let orig = conf.clone();
+        let literacy: Vec<f32> = sim.agents.iter().map(|a| a.media_literacy).collect();
+
+        let recession = sim.apply_policy(&Policy::Recession(0.5), &mut conf, &mut rng);
+        let literate = sim.apply_policy(&Policy::MediaLiteracy(0.2), &mut conf, &mut rng);
+        let taxed = sim.apply_policy(&Policy::TaxAdvertising(0.2), &mut conf, &mut rng);
+        let founded = sim.apply_policy(&Policy::FoundPlatforms(2), &mut conf, &mut rng);
+        let grown = sim.apply_policy(&Policy::PopulationChange(50), &mut conf, &mut rng);
+        assert_eq!(sim.active_policies().len(), 5);
+        assert!(conf.economy < orig.economy);
+
+        assert!(sim.revert_policy(recession, &mut conf, &mut rng));
+        assert!(!sim.revert_policy(recession, &mut conf, &mut rng));
+        assert_eq!(conf.economy, orig.economy);
+        assert_eq!(conf.unsubscribe_lag, orig.unsubscribe_lag);
+        assert_eq!(conf.revenue_per_ad, orig.revenue_per_ad);
+
+        sim.revert_policy(literate, &mut conf, &mut rng);
+        assert!(sim.agents.iter().zip(&literacy).all(|(a, l)| (a.media_literacy - l).abs() < 1e-6));
+
+        sim.revert_policy(taxed, &mut conf, &mut rng);
+        assert_eq!(sim.advertising_tax(), 0.);
+
+        sim.revert_policy(founded, &mut conf, &mut rng);
+        assert_eq!(sim.platforms.iter().filter(|p| p.active).count(), conf.n_platforms);
+
+        sim.revert_policy(grown, &mut conf, &mut rng);
+        assert_eq!(sim.population(), 200);
+        assert!(sim.active_policies().is_empty());
+        sim.step(&conf, &mut rng);
+    }
+
+    #[test]
+    fn policy_duration() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+
+        sim.apply_policy_for(&Policy::SubsidizeProduction(10.), Some(2), &mut conf, &mut rng);
+        sim.apply_policy(&Policy::TaxAdvertising(0.2), &mut conf, &mut rng);
+        for _ in 0..2 {
+            sim.expire_policies(&mut conf, &mut rng);
+            sim.step(&conf, &mut rng);
+            assert_eq!(sim.subsidy(), 10.);
+        }
+        sim.expire_policies(&mut conf, &mut rng);
+        assert_eq!(sim.subsidy(), 0.);
+        assert_eq!(sim.advertising_tax(), 0.2);
+        assert_eq!(sim.active_policies().len(), 1);
+        assert_eq!(sim.active_policies()[0].until, None);
+    }
+
+    #[test]
+    fn overlapping_policies() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+
+        // Reverting the earlier one leaves the later in effect
+        let first = sim.apply_policy(&Policy::TaxAdvertising(0.2), &mut conf, &mut rng);
+        let second = sim.apply_policy(&Policy::TaxAdvertising(0.4), &mut conf, &mut rng);
+        sim.revert_policy(first, &mut conf, &mut rng);
+        assert_eq!(sim.advertising_tax(), 0.4);
+        sim.revert_policy(second, &mut conf, &mut rng);
+        assert_eq!(sim.advertising_tax(), 0.);
+
+        // Reverting the later one goes back to the earlier
+        let first = sim.apply_policy(&Policy::SubsidizeProduction(10.), &mut conf, &mut rng);
+        let second = sim.apply_policy(&Policy::SubsidizeProduction(20.), &mut conf, &mut rng);
+        sim.revert_policy(second, &mut conf, &mut rng);
+        assert_eq!(sim.subsidy(), 10.);
+        sim.revert_policy(first, &mut conf, &mut rng);
+        assert_eq!(sim.subsidy(), 0.);
+    }
+
+    #[test]
+    fn publisher_market() {
+        let mut conf = test_conf();
+        conf.n_publishers = 5;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+
+        // Everyone goes bankrupt
+        conf.publisher.bankruptcy_budget = 1e9;
+        conf.publisher.bankruptcy_steps = 2;
+        sim.step(&conf, &mut rng);
+        assert!(sim.publishers.iter().all(|p| p.active));
+        assert!(sim.market_events().is_empty());
+        sim.step(&conf, &mut rng);
+        assert_eq!(sim.market_events(), &(0..5).map(MarketEvent::Exit).collect::<Vec<_>>()[..]);
+        assert!(sim.publishers.iter().all(|p| !p.active && p.subscribers == 0));
+        assert!(sim.outboxes().iter().all(|o| o.is_empty()));
+        assert!(sim.agents.iter().all(|a| a.subscriptions.is_empty()));
+        assert!(sim.publishers.iter().all(|p| p.content.len() > 0));
+
+        // Nobody is subscribed, so a new Publisher enters
+        conf.publisher.bankruptcy_steps = 0;
+        conf.publisher.entry_rate = 1.;
+        sim.step(&conf, &mut rng);
+        assert_eq!(sim.market_events(), &[MarketEvent::Entry(5)]);
+        assert_eq!(sim.publishers.len(), 6);
+        assert_eq!(sim.outboxes().len(), 6);
+        assert!(sim.publishers[5].active);
+        assert!(sim.agents.iter().all(|a| a.relevancies.len() == 6));
+        assert!(sim.distances.values().all(|d| d.len() == 6));
+
+        // Only the new Publisher gets pitched to
+        conf.publisher.entry_rate = 0.;
+        sim.step(&conf, &mut rng);
+        assert!(sim.outboxes()[..5].iter().all(|o| o.is_empty()));
+    }
+
+    #[test]
+    fn leave_platforms() {
+        let mut conf = test_conf();
+        conf.n_platforms = 3;
+        conf.base_signup_rate = 0.5;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+        let before = sim.agent_platforms().to_vec();
+        assert!(before.iter().any(|p_ids| !p_ids.is_empty()));
+
+        // Nothing is worth staying for
+        conf.platform.leave_value = 1.1;
+        sim.step(&conf, &mut rng);
+        for (p_ids, p_ids_) in before.iter().zip(sim.agent_platforms()) {
+            assert!(p_ids.is_disjoint(p_ids_));
+        }
+        for p in &sim.platforms {
+            let n_users = sim.agent_platforms().iter().filter(|p_ids| p_ids.contains(&p.id)).count();
+            assert_eq!(p.n_users(), n_users);
+        }
+    }
+
+    #[test]
+    fn content_moderation() {
+        let mut conf = test_conf();
+        conf.n_platforms = 2;
+        conf.base_signup_rate = 0.5;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+        let on_platforms = |sim: &Simulation| sim.cascades().values()
+            .flat_map(|c| c.events.iter())
+            .filter(|e| matches!(e.channel, Channel::Platform(_)))
+            .count();
+        assert!(on_platforms(&sim) > 0);
+
+        // Everything is removed
+        let id = sim.apply_policy(&Policy::ModerateExtremeValues(0., 0.), &mut conf, &mut rng);
+        assert!(sim.platforms.iter().all(|p| p.moderation.len() == 1));
+        let before = on_platforms(&sim);
+        sim.step(&conf, &mut rng);
+        assert_eq!(on_platforms(&sim), before);
+        assert!(sim.platforms.iter().map(|p| p.moderated.removed).sum::<usize>() > 0);
+
+        sim.revert_policy(id, &mut conf, &mut rng);
+        assert!(conf.platform.moderation.is_empty());
+        assert!(sim.platforms.iter().all(|p| p.moderation.is_empty()));
+        sim.step(&conf, &mut rng);
+        assert!(sim.platforms.iter().all(|p| p.moderated.removed == 0));
+    }
+
+    #[test]
+    fn revert_equal_moderation() {
+        let mut conf = test_conf();
+        let configured = Rule { flag: Flag::ExtremeValues, threshold: 0.5, visibility: 0., policy: None };
+        conf.platform.moderation = vec![configured];
+        let (mut sim, mut rng) = test_sim(&conf);
+
+        // The same Rule as the config's, twice
+        let first = sim.apply_policy(&Policy::ModerateExtremeValues(0.5, 0.), &mut conf, &mut rng);
+        let second = sim.apply_policy(&Policy::ModerateExtremeValues(0.5, 0.), &mut conf, &mut rng);
+        assert!(sim.platforms.iter().all(|p| p.moderation.len() == 3));
+
+        sim.revert_policy(first, &mut conf, &mut rng);
+        assert_eq!(conf.platform.moderation, vec![configured, Rule { policy: Some(second), ..configured }]);
+        sim.revert_policy(second, &mut conf, &mut rng);
+        assert_eq!(conf.platform.moderation, vec![configured]);
+        assert!(sim.platforms.iter().all(|p| p.moderation == vec![configured]));
+    }
+
+    #[test]
+    fn misinformation() {
+        let mut conf = test_conf();
+        conf.false_rate = 0.5;
+        conf.false_penalty = 0.1;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..10 {
+            sim.step(&conf, &mut rng);
+        }
+        let n_false = sim.content().iter().filter(|c| c.body.is_false()).count();
+        assert!(n_false > 0 && n_false < sim.content().len());
+        assert!(sim.content().iter().map(|c| c.stats.flags()).sum::<usize>() > 0);
+    }
+
+    #[test]
+    fn fact_checkers() {
+        let mut conf = test_conf();
+        conf.false_rate = 0.5;
+        let (mut sim, mut rng) = test_sim(&conf);
+        assert!(sim.fact_checkers.is_empty());
+        for _ in 0..3 {
+            sim.step(&conf, &mut rng);
+        }
+        assert!(sim.content().iter().all(|c| c.label.get().is_none()));
+
+        let id = sim.apply_policy(&Policy::FoundFactCheckers(2), &mut conf, &mut rng);
+        assert_eq!(sim.fact_checkers.len(), 2);
+        for _ in 0..3 {
+            sim.step(&conf, &mut rng);
+        }
+        let n_labeled = |sim: &Simulation| sim.content().iter().filter(|c| c.label.get().is_some()).count();
+        assert!(n_labeled(&sim) > 0);
+        assert!(sim.content().iter()
+                .filter(|c| c.label.get().is_some())
+                .all(|c| (c.label.get() == Some(Verdict::False)) == c.body.is_false()));
+
+        // Nothing more is labeled
+        sim.revert_policy(id, &mut conf, &mut rng);
+        assert!(sim.fact_checkers.iter().all(|f| !f.active));
+        let before = n_labeled(&sim);
+        sim.step(&conf, &mut rng);
+        assert!(n_labeled(&sim) <= before);
+    }
+
+    #[test]
+    fn bots() {
+        let mut conf = test_conf();
+        conf.n_bots = 5;
+        conf.bot.values = vec![0.9, -0.9];
+        let (mut sim, mut rng) = test_sim(&conf);
+        let bots: Vec<AgentId> = (200..205).collect();
+        assert_eq!(sim.agents.len(), 205);
+        for b_id in &bots {
+            assert!(sim.agents[*b_id].bot);
+            assert!(sim.network.following_ids(b_id).is_empty());
+        }
+
+        // Each bot posts its own content and
+        // reshares everything the others posted
+        sim.produce(&conf, &mut rng);
+        sim.coordinate(&conf, &mut rng);
+        for b_id in &bots {
+            assert_eq!(sim.agent_platforms[*b_id].len(), conf.max_platforms);
+            assert_eq!(sim.share_queues()[*b_id].len(), 5 + 4 * 5);
+            assert!(sim.share_queues()[*b_id].iter()
+                    .all(|sc| sc.content.body.values.as_slice() == [0.9, -0.9]));
+            assert!(sim.agents[*b_id].content.iter().all(|c| c.stats.shares() == 4));
+        }
+        sim.consume(&conf, &mut rng);
+        sim.step += 1;
+
+        // Bots aren't swayed
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+        for b_id in &bots {
+            assert_eq!(sim.agents[*b_id].values.as_slice(), [0.9, -0.9]);
+        }
+
+        // Platforms take down what they think bots posted
+        sim.apply_policy(&Policy::ModerateBots(10., 0.), &mut conf, &mut rng);
+        sim.produce(&conf, &mut rng);
+        sim.coordinate(&conf, &mut rng);
+        sim.consume(&conf, &mut rng);
+        let p_id = *sim.agent_platforms[bots[0]].iter().next().unwrap();
+        assert!(sim.platforms[p_id].moderated.removed >= bots.len() * 5);
+    }
+
+    #[test]
+    fn rewiring() {
+        let mut conf = test_conf();
+        let (mut sim, mut rng) = test_sim(&conf);
+        let n_ties = |sim: &Simulation| sim.network.nodes()
+            .map(|a_id| sim.network.following_ids(&a_id).len())
+            .sum::<usize>();
+
+        // Nothing changes by default
+        let before = n_ties(&sim);
+        sim.rewire(&conf, &mut rng);
+        assert_eq!(n_ties(&sim), before);
+
+        // Ties to distrusted friends are dropped
+        let a_id = sim.network.nodes()
+            .filter(|a_id| !sim.network.following_ids(a_id).is_empty())
+            .min()
+            .unwrap();
+        let b_id = sim.network.following_ids(&a_id)[0];
+        sim.agents[a_id].trust.insert(b_id, 0.);
+        for id in &[a_id, b_id] {
+            if !sim.platforms[0].is_signed_up(id) {
+                sim.platforms[0].signup(*id);
+                sim.agent_platforms[*id].insert(0);
+            }
+        }
+        if !sim.platforms[0].following_ids(&a_id).contains(&b_id) {
+            sim.platforms[0].follow(&a_id, &b_id);
+        }
+        conf.dissolve_trust = 0.1;
+        sim.rewire(&conf, &mut rng);
+        assert!(!sim.network.following_ids(&a_id).contains(&b_id));
+        assert!(!sim.platforms[0].following_ids(&a_id).contains(&b_id));
+        assert_eq!(n_ties(&sim), before - 1);
+        assert_eq!(sim.network.n_edges(), n_ties(&sim));
+
+        // New ties form
+        conf.rewire_rate = 1.;
+        for _ in 0..5 {
+            sim.rewire(&conf, &mut rng);
+        }
+        assert!(n_ties(&sim) > before);
+        assert_eq!(sim.network.n_edges(), n_ties(&sim));
+    }
+
+    #[test]
+    fn imported_network() {
+        let dir = std::env::temp_dir();
+        let edges = dir.join("jeco_network_test.csv");
+        let nodes = dir.join("jeco_network_test_nodes.csv");
+        std::fs::write(&edges, "source,target\nx,y\ny,z\nz,x\n").unwrap();
+        std::fs::write(&nodes, "id,row,col,values\nz,1,2,0.5 -0.5\nw,,,\n").unwrap();
+
+        let mut conf = SimulationConfig::default();
+        conf.network_file = Some(NetworkFile {
+            path: edges.to_str().unwrap().to_string(),
+            nodes: Some(nodes.to_str().unwrap().to_string()),
+            directed: false,
+        });
+        let (mut sim, mut rng) = test_sim(&conf);
+
+        // Nodes are z, w, x, y in that order
+        assert_eq!(sim.agents.len(), 4);
+        assert_eq!(sim.agents[0].location, (1, 2));
+        assert!(sim.grid[&(1, 2)].contains(&0));
+        assert_eq!(sim.agents[0].values.as_slice(), [0.5, -0.5]);
+        assert!(sim.network.following_ids(&1).is_empty());
+        assert_eq!(sim.network.following_ids(&2).iter().sorted().collect::<Vec<_>>(), vec![&0, &3]);
+        assert_eq!(sim.network.following_ids(&0).iter().sorted().collect::<Vec<_>>(), vec![&2, &3]);
+        assert_eq!(sim.grid.values().map(|a_ids| a_ids.len()).sum::<usize>(), 4);
+        sim.step(&conf, &mut rng);
+
+        std::fs::remove_file(edges).unwrap();
+        std::fs::remove_file(nodes).unwrap();
+    }
+
+    #[test]
+    fn platform_shutdown() {
+        let mut conf = test_conf();
+        conf.n_platforms = 3;
+        conf.base_signup_rate = 0.5;
+        conf.max_platforms = 2;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+        assert!(sim.platforms.iter().all(|p| p.budget > 0.));
+
+        let users = sim.platforms[0].users();
+        assert!(!users.is_empty());
+        conf.platform.shutdown_steps = 1;
+        sim.platforms[0].budget = -1e9;
+        sim.step(&conf, &mut rng);
+        assert_eq!(sim.market_events(), &[MarketEvent::PlatformExit(0)]);
+        assert!(!sim.platforms[0].active);
+        assert_eq!(sim.platforms[0].n_users(), 0);
+        assert!(sim.agent_platforms().iter().all(|p_ids| !p_ids.contains(&0)));
+
+        // Former users moved to another Platform
+        for a_id in users {
+            assert!(!sim.agent_platforms()[a_id].is_empty());
+        }
+
+        // And nobody signs up to it again
+        sim.step(&conf, &mut rng);
+        assert_eq!(sim.platforms[0].n_users(), 0);
+    }
+
+    #[test]
+    fn subscription_pricing() {
+        let mut conf = test_conf();
+        conf.n_publishers = 5;
+        conf.subscribe_trust = 0.5;
+        conf.publisher.pricing = Pricing::Fixed(1.);
+        let (mut sim, mut rng) = test_sim(&conf);
+        for _ in 0..10 {
+            sim.step(&conf, &mut rng);
+        }
+
+        // Everyone subscribed paid
+        let n_subscribers: usize = sim.agents.iter().map(|a| a.subscriptions.len()).sum();
+        let paid: f32 = sim.publishers.iter().map(|p| p.subscription_revenue).sum();
+        assert!(n_subscribers > 0);
+        assert_eq!(paid, n_subscribers as f32);
+        assert!(sim.agents.iter().all(|a| a.subscriptions.is_empty() || a.resources >= 0.));
+
+        // Paying subscribers aren't counted twice
+        assert!(sim.publishers.iter().all(|p| p.regular_revenue() == 0.));
+
+        // Every Publisher learns a price, whether or not
+        // they sell ads. Learning resets their expenses.
+        conf.publisher.pricing = Pricing::Learned(5.);
+        let mut sim = Simulation::new(&conf, &mut rng);
+        assert!(sim.publishers.iter().all(|p| p.price >= 0. && p.price <= 5.));
+        for p in &mut sim.publishers {
+            p.ads = 0.;
+            p.expenses = 1e9;
+        }
+        sim.step(&conf, &mut rng);
+        assert!(sim.publishers.iter().filter(|p| p.active).all(|p| p.expenses < 1e9));
+    }
+
     #[test]
     fn relevances() {
         let mut last = 1.;
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Serialize, Deserialize};
+use super::sim::Simulation;
+use super::config::SimulationConfig;
+use super::content::{Content, content_ref};
+
+// Bump whenever a change to the simulation's
+// state would make older snapshots unreadable
+pub static SNAPSHOT_VERSION: u32 = 24;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+
+    // Steps run so far
+    step: usize,
+
+    // The RNG is restored by re-seeding
+    // and skipping ahead to where it left off
+    seed: u64,
+    word_pos: u128,
+}
+
+// A Simulation frozen at a given step,
+// along with everything needed to continue it
+// exactly as if it had never been interrupted.
+pub struct Snapshot {
+    pub step: usize,
+    pub seed: u64,
+    pub conf: SimulationConfig,
+    pub sim: Simulation,
+    pub rng: ChaCha20Rng,
+}
+
+// The file is a sequence of: header, config,
+// content table, simulation. The content table
+// has to be loaded before the simulation so that
+// references to content can be resolved.
+// Assumes `rng` was created with `seed_from_u64(seed)`.
+pub fn save(path: &Path, step: usize, seed: u64, conf: &SimulationConfig, sim: &Simulation, rng: &ChaCha20Rng) -> bincode::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    let header = Header {
+        version: SNAPSHOT_VERSION,
+        step,
+        seed,
+        word_pos: rng.get_word_pos(),
+    };
+    let content: Vec<&Content> = sim.referenced_content().into_iter()
+        .map(|c| c.as_ref())
+        .collect();
+    bincode::serialize_into(&mut writer, &header)?;
+    bincode::serialize_into(&mut writer, conf)?;
+    bincode::serialize_into(&mut writer, &content)?;
+    bincode::serialize_into(&mut writer, sim)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> bincode::Result<Snapshot> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let header: Header = bincode::deserialize_from(&mut reader)?;
+    if header.version != SNAPSHOT_VERSION {
+        return Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "snapshot version {} is not supported (expected {})",
+            header.version, SNAPSHOT_VERSION))));
+    }
+    let conf: SimulationConfig = bincode::deserialize_from(&mut reader)?;
+    let content: Vec<Content> = bincode::deserialize_from(&mut reader)?;
+    let sim: Simulation = content_ref::with_registry(content, || {
+        bincode::deserialize_from(&mut reader)
+    })?;
+
+    let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(header.seed);
+    rng.set_word_pos(header.word_pos);
+
+    Ok(Snapshot {
+        step: header.step,
+        seed: header.seed,
+        conf,
+        sim,
+        rng,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::content::{ContentId, ContentExpiry};
+    use super::super::testing::test_conf;
+    use rand::Rng;
+    use std::env;
+
+    fn fingerprint(sim: &Simulation, rng: &mut ChaCha20Rng) -> (Vec<f32>, Vec<f32>, Vec<usize>, Vec<ContentId>, usize, u64) {
+        let values = sim.agents.iter().flat_map(|a| a.values.iter().cloned().collect::<Vec<f32>>()).collect();
+        let budgets = sim.publishers.iter().map(|p| p.budget).collect();
+        let shares = sim.n_shares();
+        let ids = sim.content().iter().map(|c| c.id).collect();
+        (values, budgets, shares, ids, sim.population(), rng.gen())
+    }
+
+    #[test]
+    fn resumed_run_matches_uninterrupted() {
+        let mut conf = test_conf();
+        conf.n_platforms = 2;
+        conf.base_signup_rate = 0.1;
+
+        // Content expires and Agents come and go
+        // both before and after the snapshot
+        conf.content_expiry = ContentExpiry::Ttl(2);
+        conf.birth_rate = 0.05;
+        conf.death_rate = 0.05;
+        let seed = 0;
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(seed);
+        let mut sim = Simulation::new(&conf, &mut rng);
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+
+        let path = env::temp_dir().join("jeco_snapshot_test.snap");
+        save(&path, 5, seed, &conf, &sim, &rng).unwrap();
+
+        for _ in 0..5 {
+            sim.step(&conf, &mut rng);
+        }
+        let expected = fingerprint(&sim, &mut rng);
+
+        let mut snapshot = load(&path).unwrap();
+        assert_eq!(snapshot.step, 5);
+        assert_eq!(snapshot.conf, conf);
+        for _ in 0..5 {
+            snapshot.sim.step(&snapshot.conf, &mut snapshot.rng);
+        }
+        let resumed = fingerprint(&snapshot.sim, &mut snapshot.rng);
+        assert_eq!(expected, resumed);
+    }
+}
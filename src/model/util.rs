@@ -1,16 +1,17 @@
 use rand::Rng;
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use std::f32::consts::E;
 use rand_distr::StandardNormal;
 use std::collections::VecDeque;
 use fnv::{FnvHashMap, FnvHashSet};
 use std::hash::Hash;
 use rand::seq::SliceRandom;
-use nalgebra::{Matrix, Dynamic, U2, VecStorage, VectorN, RowVectorN};
+use serde::{Serialize, Deserialize};
+use nalgebra::{DMatrix, DVector, RowDVector};
 
-// 2 so can be plotted in 2d
-pub static VECTOR_SIZE: u32 = 2;
-pub type Vector = VectorN<f32, U2>;
+// Dimensions are set by the config,
+// see `topic_dims` and `value_dims`
+pub type Vector = DVector<f32>;
 
 // Exponentially weighted moving average
 pub static EWMA_ALPHA: f32 = 0.7;
@@ -19,16 +20,16 @@ pub fn ewma(mu: f32, prev: f32) -> f32 {
 }
 
 // Bayesian normal update
-pub type Sample = Matrix<f32, Dynamic, U2, VecStorage<f32, Dynamic, U2>>;
-pub type SampleRow = RowVectorN<f32, U2>;
+pub type Sample = DMatrix<f32>;
+pub type SampleRow = RowDVector<f32>;
 static EPSILON: f32 = 1e-10;
-pub fn bayes_update(prior: (Vector, Vector), sample: Sample) -> (Vector, Vector) {
+pub fn bayes_update(prior: &(Vector, Vector), sample: Sample) -> (Vector, Vector) {
     let (prior_mu, prior_var) = prior;
     let sample_mu = sample.row_mean().transpose();
     let sample_var = sample.row_variance().transpose().add_scalar(EPSILON);
     let denom = prior_var + &sample_var;
-    let post_mu = (sample_var.component_mul(&prior_mu) + prior_var.component_mul(&sample_mu)).component_div(&denom);
-    let post_var = sample_var.component_mul(&prior_var).component_div(&denom);
+    let post_mu = (sample_var.component_mul(prior_mu) + prior_var.component_mul(&sample_mu)).component_div(&denom);
+    let post_var = sample_var.component_mul(prior_var).component_div(&denom);
     (post_mu, post_var)
 }
 
@@ -63,48 +64,48 @@ pub fn sigmoid(x: f32) -> f32 {
 
 static NORMAL_SCALE: f32 = 1.0;
 static NORMAL_SCALE_TIGHT: f32 = 0.05;
-pub fn normal_range(rng: &mut StdRng) -> f32 {
+pub fn normal_range(rng: &mut ChaCha20Rng) -> f32 {
     let mut val: f32 = rng.sample(StandardNormal);
     val *= NORMAL_SCALE;
     clamp(val, -1., 1.)
 }
 
-pub fn normal_range_mu(mu: f32, rng: &mut StdRng) -> f32 {
+pub fn normal_range_mu(mu: f32, rng: &mut ChaCha20Rng) -> f32 {
     let mut val: f32 = rng.sample(StandardNormal);
     val *= NORMAL_SCALE;
     val += mu;
     clamp(val, -1., 1.)
 }
 
-pub fn normal_range_mu_tight(mu: f32, rng: &mut StdRng) -> f32 {
+pub fn normal_range_mu_tight(mu: f32, rng: &mut ChaCha20Rng) -> f32 {
     let mut val: f32 = rng.sample(StandardNormal);
     val *= NORMAL_SCALE_TIGHT;
     val += mu;
     clamp(val, -1., 1.)
 }
 
-pub fn normal_p(rng: &mut StdRng) -> f32 {
+pub fn normal_p(rng: &mut ChaCha20Rng) -> f32 {
     let mut val = rng.sample(StandardNormal);
     val *= NORMAL_SCALE;
     val = (val + 0.5) * 2.;
     clamp(val, 0., 1.)
 }
 
-pub fn normal_p_mu(mu: f32, rng: &mut StdRng) -> f32 {
+pub fn normal_p_mu(mu: f32, rng: &mut ChaCha20Rng) -> f32 {
     let mut val = rng.sample(StandardNormal);
     val *= NORMAL_SCALE;
     val += mu;
     clamp(val, 0., 1.)
 }
 
-pub fn normal_p_mu_tight(mu: f32, rng: &mut StdRng) -> f32 {
+pub fn normal_p_mu_tight(mu: f32, rng: &mut ChaCha20Rng) -> f32 {
     let mut val = rng.sample(StandardNormal);
     val *= NORMAL_SCALE_TIGHT;
     val += mu;
     clamp(val, 0., 1.)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LimitedQueue<T> {
     _vec: Vec<T>,
     capacity: usize,
@@ -128,7 +129,7 @@ impl<T> LimitedQueue<T> {
         self._vec.truncate(self.capacity);
     }
 
-    pub fn iter(&self) -> std::slice::Iter<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self._vec.iter()
     }
 
@@ -136,13 +137,17 @@ impl<T> LimitedQueue<T> {
         self._vec.len()
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     pub fn as_slice(&self) -> &[T] {
         self._vec.as_slice()
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LimitedSet<T: Eq + Hash + Clone> {
     _vec: VecDeque<T>,
     _set: FnvHashSet<T>,
@@ -171,7 +176,7 @@ impl<T: Eq + Hash + Clone> LimitedSet<T> {
         self._set.insert(val);
     }
 
-    pub fn iter(&self) -> std::collections::vec_deque::Iter<T> {
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, T> {
         self._vec.iter()
     }
 
@@ -184,27 +189,58 @@ impl<T: Eq + Hash + Clone> LimitedSet<T> {
     }
 }
 
+// Serializes a hash map in key order and rebuilds
+// it by inserting in that order. Only maps that are
+// filled once in key order and never removed from
+// iterate the same once restored; anything else may not,
+// so it should only be looked up. State that's removed
+// from and iterated where order matters (e.g. where it
+// feeds into random draws) is kept in a BTreeMap instead.
+pub mod sorted_map {
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use fnv::FnvHashMap;
+    use std::hash::Hash;
+
+    pub fn serialize<K, V, S>(map: &FnvHashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+        where K: Serialize + Ord + Hash, V: Serialize, S: Serializer {
+        let mut entries: Vec<(&K, &V)> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<FnvHashMap<K, V>, D::Error>
+        where K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>, D: Deserializer<'de> {
+        let entries: Vec<(K, V)> = Vec::deserialize(deserializer)?;
+        let mut map = FnvHashMap::default();
+        for (k, v) in entries {
+            map.insert(k, v);
+        }
+        Ok(map)
+    }
+}
+
 // Quality, Ads
 // ParamsKey is separate b/c f32s aren't hashable
 pub type Params = (f32, f32, f32, f32);
 pub type ParamsKey = (usize, usize, usize, usize);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Learner {
     learners: [SubLearner; 4]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubLearner {
     min: f32,
     max: f32,
     steps: usize,
     param: usize,
+    #[serde(with = "sorted_map")]
     history: FnvHashMap<usize, f32>,
 }
 
 impl SubLearner {
-    fn new(steps: usize, min: f32, max: f32, rng: &mut StdRng) -> SubLearner {
+    pub fn new(steps: usize, min: f32, max: f32, rng: &mut ChaCha20Rng) -> SubLearner {
         let mut history = FnvHashMap::default();
         let keys: Vec<usize> = (0..steps+1).collect();
         for k in &keys {
@@ -220,12 +256,12 @@ impl SubLearner {
         }
     }
 
-    fn learn(&mut self, reward: f32) {
+    pub fn learn(&mut self, reward: f32) {
         let v = self.history.get_mut(&self.param).unwrap();
         *v = ewma(reward, *v);
     }
 
-    fn decide(&mut self, rng: &mut StdRng) {
+    pub fn decide(&mut self, rng: &mut ChaCha20Rng) {
         // Square weights to bias towards better-performing params
         let keys: Vec<&usize> = self.history.keys().collect();
         let key = keys.choose_weighted(rng, |k| f32::max(0., *(self.history.get(k).unwrap()) + 1.).powi(2)).unwrap();
@@ -259,7 +295,7 @@ static STEPS_ATTENTION: usize = 10;
 
 
 impl Learner {
-    pub fn new(rng: &mut StdRng) -> Learner {
+    pub fn new(rng: &mut ChaCha20Rng) -> Learner {
         let learners = [
             SubLearner::new(STEPS_DEPTH, MIN_DEPTH, MAX_DEPTH, rng),
             SubLearner::new(STEPS_SPECTACLE, MIN_SPECTACLE, MAX_SPECTACLE, rng),
@@ -279,7 +315,7 @@ impl Learner {
         }
     }
 
-    pub fn decide(&mut self, rng: &mut StdRng) {
+    pub fn decide(&mut self, rng: &mut ChaCha20Rng) {
         for learner in &mut self.learners {
             learner.decide(rng);
         }
@@ -295,7 +331,7 @@ impl Learner {
 mod tests {
     use super::*;
     use rand::SeedableRng;
-    use rand::rngs::StdRng;
+    use rand_chacha::ChaCha20Rng;
 
     #[test]
     fn test_limited_queue() {
@@ -321,7 +357,7 @@ mod tests {
 
     #[test]
     fn test_learner() {
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut learner = Learner::new(&mut rng);
         for i in 0..100 {
             let params = learner.get_params();
@@ -365,7 +401,7 @@ mod tests {
         // Check that normal sampler is tight enough
         let mu = 1.;
         let max_distance = 0.1;
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut count = 0;
         let total = 100;
         for _ in 0..total {
@@ -385,7 +421,7 @@ mod tests {
         // Check that normal sampler is tight enough
         let mu = 0.5;
         let max_distance = 0.1;
-        let mut rng: StdRng = SeedableRng::seed_from_u64(0);
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(0);
         let mut count = 0;
         let total = 100;
         for _ in 0..total {
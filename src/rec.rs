@@ -1,21 +1,27 @@
-use super::model::{Simulation, Agent, AgentId, PublisherId, Values, Position};
+use super::model::{Simulation, Agent, AgentId, Publisher, PublisherId, Values, Position, ModerationStats, Cascade, ContentId};
 use super::config::Config;
 use chrono::{DateTime, Utc};
 use fnv::FnvHashMap;
-use rand::rngs::StdRng;
+use rand_chacha::ChaCha20Rng;
 use rand::seq::SliceRandom;
 use serde_json::{json, Value};
 use std::fs;
 use std::os::unix::fs::symlink;
 use std::path::Path;
-use std::sync::Arc;
 use redis::Commands;
+use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct Recorder {
     history: Vec<Value>,
     agents: Vec<AgentId>,
     publishers: Vec<PublisherId>,
     init_values: Vec<Values>,
+
+    // Cascades of content that expired during the run,
+    // since they're no longer in the Simulation by the end
+    #[serde(default)]
+    cascades: Vec<Value>,
 }
 
 pub fn mean_usize(vec: &Vec<usize>) -> f32 {
@@ -33,15 +39,55 @@ pub fn min_f32(vec: &Vec<f32>) -> f32 {
     vec.iter().fold(1./0., |a, &b| f32::min(a, b))
 }
 
+// Mean distance of Agents' values
+// from the average of everyone's values.
+// Bots are left out since their values are fixed.
+pub fn polarization(sim: &Simulation) -> f32 {
+    let living: Vec<&Agent> = sim.agents.iter().filter(|a| a.alive && !a.bot).collect();
+    if living.is_empty() {
+        return 0.;
+    }
+    let center = living.iter()
+        .fold(Values::zeros(living[0].values.len()), |acc, a| acc + &a.values) / living.len() as f32;
+    living.iter().map(|a| (&a.values - &center).norm()).sum::<f32>() / living.len() as f32
+}
+
+// Which side of center each value falls on,
+// so Agents who lean the same ways share a community
+pub fn value_orthant(values: &Values) -> usize {
+    values.iter().enumerate()
+        .filter(|(_, v)| **v >= 0.)
+        .fold(0, |acc, (i, _)| acc | 1 << i)
+}
+
+// How much living Agents of one kind,
+// authentic or bots, put out and how far it went
+pub fn kind_stats(sim: &Simulation, bots: bool) -> Value {
+    let agents: Vec<&Agent> = sim.agents.iter().filter(|a| a.alive && a.bot == bots).collect();
+    let reach: Vec<f32> = agents.iter().map(|a| a.reach).collect();
+    let live = sim.content();
+    let content: Vec<_> = live.iter().filter(|c| sim.agents[c.author].bot == bots).collect();
+    let total_shares = live.iter().map(|c| c.stats.shares()).sum::<usize>();
+    let shares = content.iter().map(|c| c.stats.shares()).sum::<usize>();
+    json!({
+        "n": agents.len(),
+        "reach": mean_f32(&reach),
+        "content": content.len() as f32/live.len() as f32,
+        "shares": shares as f32/total_shares as f32,
+    })
+}
+
 
 impl Recorder {
-    pub fn new(sim: &Simulation, mut rng: &mut StdRng) -> Recorder {
-        let a_sample_size = (0.2 * sim.agents.len() as f32) as usize;
-        let agents: Vec<AgentId> = sim.agents
+    pub fn new(sim: &Simulation, mut rng: &mut ChaCha20Rng) -> Recorder {
+        // Only authentic Agents are tracked
+        let living: Vec<&Agent> = sim.agents.iter().filter(|a| a.alive && !a.bot).collect();
+        let a_sample_size = (0.2 * living.len() as f32) as usize;
+        let agents: Vec<AgentId> = living
             .choose_multiple(&mut rng, a_sample_size)
             .map(|a| a.id)
             .collect();
-        let init_values = agents.iter().map(|id| sim.agents[*id].values).collect();
+        let init_values = agents.iter().map(|id| sim.agents[*id].values.clone()).collect();
 
         let p_sample_size = 10;
         let publishers: Vec<PublisherId> = sim.publishers
@@ -53,19 +99,25 @@ impl Recorder {
             history: Vec::new(),
             agents: agents,
             publishers: publishers,
-            init_values: init_values
+            init_values,
+            cascades: Vec::new(),
         }
     }
 
     pub fn record(&mut self, step: usize, sim: &Simulation) {
-        let agents: Vec<&Agent> = self.agents.iter().map(|id| &sim.agents[*id]).collect();
+        // Sampled Agents that are still around
+        let (agents, init_values): (Vec<&Agent>, Vec<&Values>) = self.agents.iter()
+            .zip(self.init_values.iter())
+            .map(|(id, init)| (&sim.agents[*id], init))
+            .filter(|(a, _)| a.alive)
+            .unzip();
         let a_sample: Vec<Value> = agents
             .iter()
             .map(|a| {
                 json!({
                     "id": a.id,
-                    "values": a.values,
-                    "interests": a.interests,
+                    "values": a.values.as_slice(),
+                    "interests": a.interests.as_slice(),
                     "location": a.location,
                 })
             })
@@ -76,8 +128,8 @@ impl Recorder {
             .map(|p| {
                 json!({
                     "id": p.id,
-                    "values": p.audience.values.0, // mean only
-                    "interests": p.audience.interests.0, // ditto
+                    "values": p.audience.values.0.as_slice(), // mean only
+                    "interests": p.audience.interests.0.as_slice(), // ditto
                 })
             })
             .collect();
@@ -94,7 +146,11 @@ impl Recorder {
                 "motive": p.motive,
                 "spectacle": p.spectacle,
                 "ads_sold": p.n_ads_sold,
-                "subscribers": p.subscribers
+                "subscribers": p.subscribers,
+                "price": p.price,
+                "subscription_revenue": p.subscription_revenue,
+                "paywall": p.paywall,
+                "active": p.active
             }));
             acc
         });
@@ -103,23 +159,54 @@ impl Recorder {
             acc.insert(p.id, json!({
                 "users": p.n_users(),
                 "data": p.data,
+                "ranker": p.ranker,
+                "budget": p.budget,
+                "revenue": p.revenue,
+                "ad_revenue": p.ad_revenue,
+                "active": p.active,
+                "moderated": p.moderated,
             }));
             acc
         });
 
+        let moderated = sim.platforms.iter().fold(ModerationStats::default(), |mut acc, p| {
+            acc.removed += p.moderated.removed;
+            acc.downranked += p.moderated.downranked;
+            acc.false_positives += p.moderated.false_positives;
+            acc.false_negatives += p.moderated.false_negatives;
+            acc
+        });
+
+        // Herfindahl index of Platforms' shares of users
+        let n_users: Vec<usize> = sim.platforms.iter().map(|p| p.n_users()).collect();
+        let total_users = n_users.iter().sum::<usize>() as f32;
+        let concentration = if total_users > 0. {
+            n_users.iter().map(|n| (*n as f32/total_users).powi(2)).sum()
+        } else {
+            0.
+        };
+
         // Top 10
         let content: Vec<Value> = sim.content_by_popularity().take(10).map(|c| {
+            let (depth, breadth) = match sim.cascade(&c.id) {
+                Some(cascade) => (cascade.depth(), cascade.breadth()),
+                None => (0, 0)
+            };
             json!({
-                "shares": Arc::strong_count(c),
-                "topics": c.body.topics,
-                "values": c.body.values,
+                "shares": c.stats.shares(),
+                "views": c.stats.views(),
+                "ad_impressions": c.stats.ad_impressions(),
+                "cascade_depth": depth,
+                "cascade_breadth": breadth,
+                "topics": c.body.topics.as_slice(),
+                "values": c.body.values.as_slice(),
                 "attention_cost": c.body.cost
             })
         }).collect();
 
         let space: Vec<(Position, usize)> = sim.grid.iter().map(|(pos, agents)| (*pos, agents.len())).collect();
 
-        let value_shifts: Vec<f32> = agents.iter().zip(self.init_values.iter())
+        let value_shifts: Vec<f32> = agents.iter().zip(init_values.iter())
             .map(|(a, b)| 1. - a.values.normalize().dot(&b.normalize())).collect();
 
         let n_shares = sim.n_shares();
@@ -136,18 +223,32 @@ impl Recorder {
             *count += 1;
         }
 
-        let n_subscribers: Vec<usize> = sim.publishers.iter().map(|p| p.subscribers).collect();
-        let n_published: Vec<usize> = sim.publishers.iter().map(|p| p.n_last_published).collect();
-        let depth: Vec<f32> = sim.publishers.iter().map(|p| p.depth).collect();
-        let spectacle: Vec<f32> = sim.publishers.iter().map(|p| p.spectacle).collect();
-        let ads: Vec<f32> = sim.publishers.iter().map(|p| p.ads).collect();
-        let reach: Vec<f32> = sim.publishers.iter().map(|p| p.reach).collect();
-        let budget: Vec<f32> = sim.publishers.iter().map(|p| p.budget).collect();
+        let cascade_depth: Vec<usize> = sim.cascades().values().map(|c| c.depth()).collect();
+        let cascade_breadth: Vec<usize> = sim.cascades().values().map(|c| c.breadth()).collect();
+        let virality: Vec<f32> = sim.cascades().values().map(|c| c.structural_virality()).collect();
+
+        // Stats only cover Publishers still in business
+        let active: Vec<&Publisher> = sim.publishers.iter().filter(|p| p.active).collect();
+        let n_subscribers: Vec<usize> = active.iter().map(|p| p.subscribers).collect();
+        let n_published: Vec<usize> = active.iter().map(|p| p.n_last_published).collect();
+        let depth: Vec<f32> = active.iter().map(|p| p.depth).collect();
+        let spectacle: Vec<f32> = active.iter().map(|p| p.spectacle).collect();
+        let ads: Vec<f32> = active.iter().map(|p| p.ads).collect();
+        let reach: Vec<f32> = active.iter().map(|p| p.reach).collect();
+        let budget: Vec<f32> = active.iter().map(|p| p.budget).collect();
         let publishability: Vec<f32> = agents.iter().map(|a| a.publishability).collect();
         let media_literacy: Vec<f32> = agents.iter().map(|a| a.media_literacy).collect();
         let resources: Vec<f32> = agents.iter().map(|a| a.resources).collect();
         let agent_reach: Vec<f32> = agents.iter().map(|a| a.reach).collect();
 
+        let fact_checkers: Vec<_> = sim.fact_checkers.iter().filter(|f| f.active).collect();
+
+        // False content still in circulation
+        let live = sim.content();
+        let false_content: Vec<_> = live.iter().filter(|c| c.body.is_false()).collect();
+        let total_shares = live.iter().map(|c| c.stats.shares()).sum::<usize>();
+        let false_shares = false_content.iter().map(|c| c.stats.shares()).sum::<usize>();
+
         let value = json!({
             "step": step,
             "space": space,
@@ -163,6 +264,20 @@ impl Recorder {
                 "mean": mean_usize(&n_followers),
             },
             "follower_dist": follower_dist,
+            "cascades": {
+                "depth": {
+                    "max": cascade_depth.iter().max(),
+                    "mean": mean_usize(&cascade_depth),
+                },
+                "breadth": {
+                    "max": cascade_breadth.iter().max(),
+                    "mean": mean_usize(&cascade_breadth),
+                },
+                "structural_virality": {
+                    "max": max_f32(&virality),
+                    "mean": mean_f32(&virality),
+                },
+            },
             "value_shifts": {
                 "max": max_f32(&value_shifts),
                 "min": min_f32(&value_shifts),
@@ -192,6 +307,8 @@ impl Recorder {
             "publishers": {
                 "audience": p_sample,
                 "sample": publishers,
+                "n_active": active.len(),
+                "events": sim.market_events(),
                 "stats": {
                     "budget": {
                         "max": max_f32(&budget),
@@ -231,39 +348,57 @@ impl Recorder {
                 }
             },
             "platforms": {
-                "sample": platforms
+                "sample": platforms,
+                "n_active": sim.platforms.iter().filter(|p| p.active).count(),
+                "concentration": concentration,
+                "moderated": moderated,
             },
-            "p_produced": sim.n_produced as f32/sim.agents.len() as f32,
+            "fact_checkers": {
+                "n_active": fact_checkers.len(),
+                "checked": fact_checkers.iter().map(|f| f.n_checked).sum::<usize>(),
+                "labeled_false": fact_checkers.iter().map(|f| f.n_labeled_false).sum::<usize>(),
+            },
+            "network": {
+                "clustering": sim.network.clustering(&self.agents),
+                "modularity": {
+                    "location": sim.network.modularity(|a_id| sim.agents[*a_id].location),
+                    "values": sim.network.modularity(|a_id| value_orthant(&sim.agents[*a_id].values)),
+                },
+            },
+            "polarization": polarization(sim),
+            "authentic": kind_stats(sim, false),
+            "inauthentic": kind_stats(sim, true),
+            "population": sim.population(),
+            "p_produced": sim.n_produced as f32/sim.population() as f32,
             "p_pitched": sim.n_pitched as f32/sim.n_produced as f32,
             "p_published": sim.n_published as f32/sim.n_produced as f32,
             "n_published": sim.n_published,
             "to_share": sim.n_will_share(),
+            "expired": sim.expired(),
+            "misinformation": {
+                "prevalence": false_content.len() as f32/live.len() as f32,
+                "shares": false_shares as f32/total_shares as f32,
+                "caught": false_content.iter().map(|c| c.stats.flags()).sum::<usize>(),
+            },
             "top_content": content
         });
         self.history.push(value);
+
+        self.cascades.extend(sim.expired_cascades().iter()
+            .map(|e| cascade_json(&e.id, e.author, e.publisher, &e.cascade)));
     }
 
-    pub fn save(&self, conf: &Config) {
+    pub fn save(&self, conf: &Config, sim: &Simulation) {
         let now: DateTime<Utc> = Utc::now();
         let now_str = now.format("%Y.%m.%d.%H.%M.%S").to_string();
-        let results = json!({
-            "history": self.history,
-            "meta": {
-                "seed": conf.seed,
-                "steps": conf.steps,
-                "conf": conf.simulation,
-            }
-        })
-        .to_string();
-
         let dir = format!("runs/{}", now_str);
-        let fname = format!("runs/{}/output.json", now_str);
 
         let path = Path::new(&dir);
         let run_path = Path::new(&now_str);
         let latest_path = Path::new("runs/latest");
         fs::create_dir(path).unwrap();
-        fs::write(fname, results).expect("Unable to write file");
+        self.write(conf, path);
+        self.write_cascades(sim, path);
         if latest_path.exists() {
             fs::remove_file(latest_path).unwrap();
         }
@@ -274,6 +409,35 @@ impl Recorder {
         println!("Wrote output to {:?}", path);
     }
 
+    // Write output to an existing directory
+    pub fn write(&self, conf: &Config, path: &Path) {
+        let results = json!({
+            "history": self.history,
+            "meta": {
+                "seed": conf.seed,
+                "steps": conf.steps,
+                "conf": conf.simulation,
+                "scenario": conf.scenario,
+            }
+        })
+        .to_string();
+
+        let fname = Path::join(path, Path::new("output.json"));
+        fs::write(fname, results).expect("Unable to write file");
+    }
+
+    // Write how each piece of content spread,
+    // including expired content, to an existing directory
+    pub fn write_cascades(&self, sim: &Simulation, path: &Path) {
+        let live: Vec<Value> = sim.content().iter()
+            .filter_map(|c| sim.cascade(&c.id).map(|cascade| cascade_json(&c.id, c.author, c.publisher, cascade)))
+            .collect();
+        let cascades: Vec<&Value> = self.cascades.iter().chain(live.iter()).collect();
+
+        let fname = Path::join(path, Path::new("cascades.json"));
+        fs::write(fname, serde_json::to_string(&cascades).unwrap()).expect("Unable to write file");
+    }
+
     pub fn sync(&self, step: usize, redis_host: &str) -> redis::RedisResult<()> {
         match self.history.get(step) {
             None => (),
@@ -282,10 +446,24 @@ impl Recorder {
                 let mut con = client.get_connection()?;
 
                 let state_serialized = snapshot.to_string();
-                con.rpush("state:history", state_serialized)?;
-                con.set("state:step", format!("{:?}", step))?;
+                let _: () = con.rpush("state:history", state_serialized)?;
+                let _: () = con.set("state:step", format!("{:?}", step))?;
             }
         }
         Ok(())
     }
 }
+
+fn cascade_json(id: &ContentId, author: AgentId, publisher: Option<PublisherId>, cascade: &Cascade) -> Value {
+    json!({
+        "id": id,
+        "author": author,
+        "publisher": publisher,
+        "size": cascade.size(),
+        "depth": cascade.depth(),
+        "breadth": cascade.breadth(),
+        "structural_virality": cascade.structural_virality(),
+        "time_to_peak": cascade.time_to_peak(),
+        "events": cascade.events,
+    })
+}
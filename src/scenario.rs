@@ -0,0 +1,106 @@
+use super::model::{Simulation, SimulationConfig, Policy};
+use rand_chacha::ChaCha20Rng;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::BufReader;
+
+// A scenario lists policies to apply
+// at given steps, in order, e.g.:
+//
+//   POLICIES:
+//     - STEP: 200
+//       POLICY: {TaxAdvertising: 0.3}
+//     - STEP: 500
+//       POLICY: {Recession: 0.5}
+//       DURATION: 200
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct Scenario {
+    pub policies: Vec<ScheduledPolicy>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct ScheduledPolicy {
+    // Applied before this step is run
+    pub step: usize,
+    pub policy: Policy,
+
+    // Steps until the policy is reverted,
+    // if it isn't permanent
+    #[serde(default)]
+    pub duration: Option<usize>,
+}
+
+impl Scenario {
+    // Policies due before `step` is run,
+    // in the order they're listed
+    pub fn due(&self, step: usize) -> impl Iterator<Item=&ScheduledPolicy> {
+        self.policies.iter().filter(move |p| p.step == step)
+    }
+
+    // Reverts expired policies first
+    pub fn apply(&self, step: usize, sim: &mut Simulation, conf: &mut SimulationConfig, rng: &mut ChaCha20Rng) {
+        sim.expire_policies(conf, rng);
+        for p in self.due(step) {
+            sim.apply_policy_for(&p.policy, p.duration, conf, rng);
+        }
+    }
+}
+
+pub fn load(path: &str) -> Scenario {
+    let file = File::open(path).expect("could not open scenario file");
+    let reader = BufReader::new(file);
+    let scenario: Scenario = serde_yaml::from_reader(reader).expect("error while reading scenario yaml");
+    for p in &scenario.policies {
+        if p.duration.is_some() && !p.policy.is_reversible() {
+            panic!("policy {:?} at step {} can't be reverted, so it can't have a duration", p.policy, p.step);
+        }
+    }
+    scenario
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::testing::{test_conf, test_sim};
+
+    #[test]
+    fn scheduled_policies() {
+        let scenario: Scenario = serde_yaml::from_str("
+POLICIES:
+    - STEP: 2
+      POLICY: {TaxAdvertising: 0.3}
+    - STEP: 4
+      POLICY: {Recession: 0.5}
+      DURATION: 3
+    - STEP: 4
+      POLICY: {SubsidizeProduction: 10}
+").unwrap();
+        assert_eq!(scenario.due(4).map(|p| &p.policy).collect::<Vec<_>>(),
+                   vec![&Policy::Recession(0.5), &Policy::SubsidizeProduction(10.)]);
+
+        let mut conf = test_conf();
+        conf.n_publishers = 5;
+        let (mut sim, mut rng) = test_sim(&conf);
+        for step in 0..5 {
+            scenario.apply(step, &mut sim, &mut conf, &mut rng);
+            sim.step(&conf, &mut rng);
+            if step < 2 {
+                assert_eq!(sim.advertising_tax(), 0.);
+            }
+        }
+        assert_eq!(sim.advertising_tax(), 0.3);
+        assert_eq!(sim.subsidy(), 10.);
+        assert_eq!(conf.economy, 0.5);
+        assert_eq!(sim.active_policies().len(), 3);
+
+        // The recession is over
+        for step in 5..8 {
+            scenario.apply(step, &mut sim, &mut conf, &mut rng);
+            sim.step(&conf, &mut rng);
+        }
+        assert_eq!(conf.economy, 1.);
+        assert_eq!(sim.active_policies().len(), 2);
+    }
+}
@@ -0,0 +1,258 @@
+use super::config::{Config, set_network_population};
+use super::rec::Recorder;
+use super::model::{Simulation, SimulationConfig};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// A sweep runs every combination of
+// the listed parameter values, for each seed.
+// Parameters are named as they are in `config.yaml`,
+// with nested fields joined by a dot, e.g.:
+//
+//   STEPS: 1000
+//   SEEDS: 5
+//   PARAMS:
+//     CONTACT_RATE: [0.1, 0.2]
+//     PUBLISHER.BASE_BUDGET:
+//       FROM: 1000
+//       TO: 3000
+//       STEP: 1000
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct SweepSpec {
+    // Defaults to the number of steps
+    // of the base config
+    #[serde(default)]
+    pub steps: Option<usize>,
+
+    pub seeds: Seeds,
+
+    #[serde(default)]
+    pub params: BTreeMap<String, ParamValues>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Seeds {
+    Count(u64),
+    List(Vec<u64>),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ParamValues {
+    List(Vec<Value>),
+    Range(Range),
+}
+
+// Inclusive range
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct Range {
+    pub from: Value,
+    pub to: Value,
+    pub step: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Run {
+    pub dir: String,
+    pub seed: u64,
+    pub params: BTreeMap<String, Value>,
+}
+
+impl Seeds {
+    pub fn to_vec(&self) -> Vec<u64> {
+        match self {
+            Seeds::Count(n) => (0..*n).collect(),
+            Seeds::List(seeds) => seeds.clone(),
+        }
+    }
+}
+
+impl ParamValues {
+    pub fn to_vec(&self) -> Vec<Value> {
+        match self {
+            ParamValues::List(vals) => vals.clone(),
+            ParamValues::Range(range) => range.to_vec(),
+        }
+    }
+}
+
+impl Range {
+    // Integer ranges stay integers,
+    // so they can be used for integer fields
+    pub fn to_vec(&self) -> Vec<Value> {
+        match (self.from.as_i64(), self.to.as_i64(), self.step.as_i64()) {
+            (Some(from), Some(to), Some(step)) => {
+                assert!(step > 0, "sweep range step must be positive");
+                (from..=to).step_by(step as usize).map(Value::from).collect()
+            },
+            _ => {
+                let from = self.from.as_f64().expect("sweep range FROM must be a number");
+                let to = self.to.as_f64().expect("sweep range TO must be a number");
+                let step = self.step.as_f64().expect("sweep range STEP must be a number");
+                assert!(step > 0., "sweep range step must be positive");
+                let n = ((to - from) / step + 1e-9).floor() as usize;
+                (0..=n).map(|i| Value::from(from + step * i as f64)).collect()
+            }
+        }
+    }
+}
+
+pub fn load_spec(path: &str) -> SweepSpec {
+    let file = File::open(path).expect("could not open sweep file");
+    let reader = BufReader::new(file);
+    serde_yaml::from_reader(reader).expect("error while reading sweep yaml")
+}
+
+// Set (possibly nested) fields of the config,
+// going through its yaml representation so that
+// any field can be swept.
+pub fn apply_params(conf: &SimulationConfig, params: &BTreeMap<String, Value>) -> SimulationConfig {
+    let mut value = serde_yaml::to_value(conf).unwrap();
+    for (key, val) in params {
+        let mut field = &mut value;
+        for part in key.split('.') {
+            field = field.as_mapping_mut()
+                .and_then(|m| m.get_mut(&Value::from(part)))
+                .unwrap_or_else(|| panic!("unknown config field {:?}", key));
+        }
+        *field = val.clone();
+    }
+    serde_yaml::from_value(value).unwrap_or_else(|e| panic!("invalid sweep value: {}", e))
+}
+
+// Every combination of parameter values, times every seed
+pub fn runs(spec: &SweepSpec) -> Vec<Run> {
+    let keys: Vec<&String> = spec.params.keys().collect();
+    let combos: Vec<Vec<Value>> = if keys.is_empty() {
+        vec![Vec::new()]
+    } else {
+        spec.params.values()
+            .map(|vals| vals.to_vec())
+            .multi_cartesian_product()
+            .collect()
+    };
+    combos.iter()
+        .cartesian_product(spec.seeds.to_vec())
+        .enumerate()
+        .map(|(i, (vals, seed))| Run {
+            dir: i.to_string(),
+            seed,
+            params: keys.iter().cloned().cloned().zip(vals.iter().cloned()).collect(),
+        })
+        .collect()
+}
+
+pub fn run(spec_path: &str, base: &Config) {
+    let spec = load_spec(spec_path);
+    let runs = runs(&spec);
+    let steps = spec.steps.unwrap_or(base.steps);
+
+    let now: DateTime<Utc> = Utc::now();
+    let dir = format!("runs/sweep.{}", now.format("%Y.%m.%d.%H.%M.%S"));
+    let path = Path::new(&dir);
+    fs::create_dir_all(path).unwrap();
+
+    let index = json!({
+        "spec": spec,
+        "steps": steps,
+        "runs": runs,
+    });
+    fs::write(path.join("index.json"), index.to_string()).expect("Unable to write file");
+    println!("Running {:?} runs into {:?}", runs.len(), path);
+
+    runs.par_iter().for_each(|run| {
+        let mut conf = base.clone();
+        conf.seed = run.seed;
+        conf.steps = steps;
+        conf.simulation = apply_params(&base.simulation, &run.params);
+        let graph = set_network_population(&mut conf.simulation);
+
+        let mut rng: ChaCha20Rng = SeedableRng::seed_from_u64(conf.seed);
+        let mut sim = Simulation::with_network(&conf.simulation, graph, &mut rng);
+        let mut recorder = Recorder::new(&sim, &mut rng);
+        for step in 0..steps {
+            if let Some(scenario) = &conf.scenario {
+                scenario.apply(step, &mut sim, &mut conf.simulation, &mut rng);
+            }
+            sim.step(&conf.simulation, &mut rng);
+            recorder.record(step, &sim);
+        }
+
+        let run_path = path.join(&run.dir);
+        fs::create_dir(&run_path).unwrap();
+        recorder.write(&conf, &run_path);
+        recorder.write_cascades(&sim, &run_path);
+        let conf_path = run_path.join("config.yaml");
+        fs::write(conf_path, serde_yaml::to_string(&conf).unwrap()).expect("Unable to write file");
+        println!("Finished run {} {}", run.dir, serde_json::to_string(&run.params).unwrap());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_combinations() {
+        let spec: SweepSpec = serde_yaml::from_str("
+SEEDS: 2
+PARAMS:
+    CONTACT_RATE: [0.1, 0.2]
+    PUBLISHER.BASE_BUDGET:
+        FROM: 1000
+        TO: 3000
+        STEP: 1000
+").unwrap();
+        let runs = runs(&spec);
+        assert_eq!(runs.len(), 2 * 3 * 2);
+
+        let conf = apply_params(&SimulationConfig::default(), &runs[runs.len()-1].params);
+        assert_eq!(conf.contact_rate, 0.2);
+        assert_eq!(conf.publisher.base_budget, 3000.);
+        assert_eq!(runs[runs.len()-1].seed, 1);
+    }
+
+    #[test]
+    fn swept_network_file() {
+        let edges = std::env::temp_dir().join("jeco_sweep_network_test.csv");
+        fs::write(&edges, "x,y\ny,z\n").unwrap();
+        let spec: SweepSpec = serde_yaml::from_str(&format!("
+SEEDS: 1
+PARAMS:
+    NETWORK_FILE: [{{PATH: {:?}}}]
+", edges.to_str().unwrap())).unwrap();
+
+        // The network decides the population
+        // once it's been swept in
+        let mut conf = apply_params(&SimulationConfig::default(), &runs(&spec)[0].params);
+        let graph = set_network_population(&mut conf);
+        assert_eq!(conf.population, 3);
+        assert_eq!(graph.unwrap().n_nodes(), 3);
+
+        fs::remove_file(edges).unwrap();
+    }
+
+    #[test]
+    fn float_range() {
+        let range = Range {
+            from: Value::from(0.1),
+            to: Value::from(0.3),
+            step: Value::from(0.1),
+        };
+        assert_eq!(range.to_vec().len(), 3);
+    }
+}
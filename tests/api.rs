@@ -0,0 +1,43 @@
+use jeco::{SimulationBuilder, Policy};
+
+#[test]
+fn build_and_step() {
+    let mut builder = SimulationBuilder::new()
+        .population(200)
+        .publishers(5)
+        .platforms(1)
+        .seed(0);
+    let (mut sim, mut rng) = builder.build();
+    assert_eq!(sim.agents.len(), 200);
+    assert_eq!(sim.publishers.len(), 5);
+    assert_eq!(sim.platforms.len(), 1);
+    assert_eq!(sim.share_queues().len(), 200);
+    assert_eq!(sim.outboxes().len(), 5);
+
+    for _ in 0..3 {
+        sim.step(builder.config(), &mut rng);
+    }
+
+    builder = builder.configure(|conf| conf.economy = 0.5);
+    let mut conf = builder.config().clone();
+    sim.apply_policy(&Policy::TaxAdvertising(0.2), &mut conf, &mut rng);
+    assert_eq!(sim.advertising_tax(), 0.2);
+}
+
+#[test]
+fn higher_dims() {
+    let builder = SimulationBuilder::new()
+        .population(200)
+        .publishers(5)
+        .platforms(1)
+        .configure(|conf| {
+            conf.topic_dims = 4;
+            conf.value_dims = 3;
+        });
+    let (mut sim, mut rng) = builder.build();
+    for _ in 0..10 {
+        sim.step(builder.config(), &mut rng);
+    }
+    assert!(sim.agents.iter().all(|a| a.interests.len() == 4 && a.values.len() == 3));
+    assert!(sim.publishers.iter().all(|p| p.audience.values.0.len() == 3));
+}